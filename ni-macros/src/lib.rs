@@ -0,0 +1,40 @@
+//! Compile-time assembler for NI programs.
+
+use ni::{assembler::assemble, encoder::encode::Encode, validator::validate};
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Error, LitStr};
+
+/// Assembles a program in the text syntax into its bytecode at compile time.
+///
+/// The program is validated, and an incorrect one fails the build. The macro expands
+/// to a `[u8; N]` array expression, so it can initialize a `const` or a `static`:
+///
+/// ```
+/// const PROGRAM: &[u8] = &ni_macros::program!("set u8 loc(0) 1\nend loc(0)");
+/// assert_eq!(PROGRAM[0], ni::common::op_codes::SET);
+/// ```
+///
+/// The bytecode is encoded for the word size of the `ni` crate the macro is built with,
+/// so the `w32` or `w64` feature of this crate has to match the one the program runs with.
+#[proc_macro]
+pub fn program(input: TokenStream) -> TokenStream {
+    let code = parse_macro_input!(input as LitStr);
+
+    match encode(&code.value()) {
+        Ok(bytes) => quote!([#(#bytes),*]).into(),
+        Err(message) => Error::new(code.span(), message).to_compile_error().into(),
+    }
+}
+
+fn encode(code: &str) -> Result<Vec<u8>, String> {
+    let program = assemble(code).map_err(|e| format!("assemble error at {}", e))?;
+    validate(&program).map_err(|e| e.to_string())?;
+
+    let mut bytes = vec![];
+    for op in &program {
+        op.encode(&mut bytes).map_err(|e| e.to_string())?;
+    }
+
+    Ok(bytes)
+}
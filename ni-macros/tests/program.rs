@@ -0,0 +1,35 @@
+use ni::{
+    assembler::assemble,
+    common::Op,
+    decoder::decode::decode,
+    executor::{ExecutionSuccess, Executor, Function},
+};
+use ni_macros::program;
+
+const INC: &[u8] = &program!(
+    "
+    set u8 loc(0) 41
+    inc u8 loc(0)
+    end loc(0)
+    "
+);
+
+#[test]
+fn program_const() {
+    let mut code = INC;
+    let mut program = vec![];
+    while !code.is_empty() {
+        let op: Op = decode(&mut code).unwrap();
+        program.push(op);
+    }
+
+    assert_eq!(
+        program,
+        assemble("set u8 loc(0) 41\ninc u8 loc(0)\nend loc(0)").unwrap()
+    );
+
+    let functions = [Function::new(8, &program)];
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+    assert_eq!(exe.run(), Ok(ExecutionSuccess::End(42)));
+}
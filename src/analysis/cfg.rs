@@ -0,0 +1,262 @@
+use crate::common::*;
+
+/// Operations `start..end` of the program with the successor blocks.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub successors: Vec<usize>,
+}
+
+/// Control-flow graph of a program.
+///
+/// Only jumps with a `Val` target are followed, jumps out of the program have no edge.
+#[derive(Debug)]
+pub struct Cfg {
+    blocks: Vec<Block>,
+    predecessors: Vec<Vec<usize>>,
+    dominators: Vec<Option<usize>>,
+}
+
+/// Index of the operation after a failed condition at `idx`.
+fn passed(program: &[Op], idx: usize) -> usize {
+    let mut next = idx + 1;
+    while program.get(next).is_some_and(Op::is_conditional) {
+        next += 1;
+    }
+
+    next + 1
+}
+
+/// Successor operations of the operation at `idx`.
+pub(crate) fn next_ops(program: &[Op], idx: usize) -> Vec<usize> {
+    let op = &program[idx];
+    let next = match op {
+        Op::End(_) | Op::Ret(..) => vec![],
+        Op::Go(Operand::Val(target)) => vec![*target as usize],
+        Op::Jmp(Operand::Val(offset)) => vec![idx.wrapping_add(*offset as IWord as isize as usize)],
+        Op::Go(_) | Op::Jmp(_) => vec![],
+        op if op.is_conditional() => vec![idx + 1, passed(program, idx)],
+        _ => vec![idx + 1],
+    };
+
+    next.into_iter().filter(|&i| i < program.len()).collect()
+}
+
+fn is_branch(op: &Op) -> bool {
+    matches!(op, Op::End(_) | Op::Ret(..) | Op::Go(_) | Op::Jmp(_)) || op.is_conditional()
+}
+
+impl Cfg {
+    pub fn build(program: &[Op]) -> Self {
+        let mut leaders = vec![false; program.len()];
+        if let Some(first) = leaders.first_mut() {
+            *first = true;
+        }
+
+        for (idx, op) in program.iter().enumerate() {
+            if is_branch(op) {
+                for next in next_ops(program, idx) {
+                    leaders[next] = true;
+                }
+
+                if let Some(leader) = leaders.get_mut(idx + 1) {
+                    *leader = true;
+                }
+            }
+        }
+
+        let mut block_of = vec![0; program.len()];
+        let mut blocks: Vec<Block> = vec![];
+        for (idx, &leader) in leaders.iter().enumerate() {
+            if leader {
+                blocks.push(Block {
+                    start: idx,
+                    end: idx + 1,
+                    successors: vec![],
+                });
+            } else {
+                blocks.last_mut().unwrap().end = idx + 1;
+            }
+
+            block_of[idx] = blocks.len() - 1;
+        }
+
+        let mut predecessors = vec![vec![]; blocks.len()];
+        for (id, block) in blocks.iter_mut().enumerate() {
+            for next in next_ops(program, block.end - 1) {
+                let succ = block_of[next];
+                if !block.successors.contains(&succ) {
+                    block.successors.push(succ);
+                    predecessors[succ].push(id);
+                }
+            }
+        }
+
+        let dominators = Self::find_dominators(&blocks, &predecessors);
+
+        Self {
+            blocks,
+            predecessors,
+            dominators,
+        }
+    }
+
+    /// Finds immediate dominators by the Cooper, Harvey and Kennedy algorithm.
+    fn find_dominators(blocks: &[Block], predecessors: &[Vec<usize>]) -> Vec<Option<usize>> {
+        if blocks.is_empty() {
+            return vec![];
+        }
+
+        // Reverse postorder from the entry block
+        let mut order = Vec::with_capacity(blocks.len());
+        let mut visited = vec![false; blocks.len()];
+        let mut stack = vec![(0, 0)];
+        visited[0] = true;
+        while let Some((id, next)) = stack.pop() {
+            if let Some(&succ) = blocks[id].successors.get(next) {
+                stack.push((id, next + 1));
+                if !visited[succ] {
+                    visited[succ] = true;
+                    stack.push((succ, 0));
+                }
+            } else {
+                order.push(id);
+            }
+        }
+        order.reverse();
+
+        let mut rank = vec![usize::MAX; blocks.len()];
+        for (i, &id) in order.iter().enumerate() {
+            rank[id] = i;
+        }
+
+        let intersect = |doms: &[Option<usize>], mut a: usize, mut b: usize| {
+            while a != b {
+                while rank[a] > rank[b] {
+                    a = doms[a].unwrap();
+                }
+                while rank[b] > rank[a] {
+                    b = doms[b].unwrap();
+                }
+            }
+            a
+        };
+
+        let mut doms = vec![None; blocks.len()];
+        doms[0] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &id in order.iter().skip(1) {
+                let new = predecessors[id]
+                    .iter()
+                    .filter(|&&p| doms[p].is_some())
+                    .fold(None, |dom, &p| match dom {
+                        None => Some(p),
+                        Some(d) => Some(intersect(&doms, d, p)),
+                    });
+
+                if doms[id] != new {
+                    doms[id] = new;
+                    changed = true;
+                }
+            }
+        }
+
+        doms[0] = None;
+        doms
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    pub fn predecessors(&self, block: usize) -> &[usize] {
+        &self.predecessors[block]
+    }
+
+    /// Index of the block containing the operation.
+    pub fn block_of(&self, idx: usize) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|b| b.start <= idx && idx < b.end)
+    }
+
+    /// Immediate dominator of the block. The entry block and unreachable blocks have none.
+    pub fn dominator(&self, block: usize) -> Option<usize> {
+        self.dominators[block]
+    }
+
+    pub fn is_reachable(&self, block: usize) -> bool {
+        block == 0 || self.dominators[block].is_some()
+    }
+
+    /// Checks whether every path from the entry to `b` goes through `a`.
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        if !self.is_reachable(b) {
+            return false;
+        }
+
+        let mut block = b;
+        loop {
+            if block == a {
+                break true;
+            }
+
+            match self.dominators[block] {
+                Some(dom) => block = dom,
+                None => break false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    #[test]
+    fn cfg_blocks() {
+        let program = assemble(
+            r#"
+            set u8 loc(0) 0
+            loop:
+            inc u8 loc(0)
+            ifl u8 loc(0) 3
+            jmp loop
+            end loc(0)
+            end 1
+            "#,
+        )
+        .unwrap();
+
+        let cfg = Cfg::build(&program);
+        let blocks: Vec<_> = cfg
+            .blocks()
+            .iter()
+            .map(|b| (b.start, b.end, b.successors.clone()))
+            .collect();
+
+        assert_eq!(
+            blocks,
+            [
+                (0, 1, vec![1]),
+                (1, 3, vec![2, 3]),
+                (3, 4, vec![1]),
+                (4, 5, vec![]),
+                (5, 6, vec![]),
+            ]
+        );
+
+        assert_eq!(cfg.predecessors(1), [0, 2]);
+        assert_eq!(cfg.block_of(2), Some(1));
+        assert_eq!(cfg.dominator(0), None);
+        assert_eq!(cfg.dominator(2), Some(1));
+        assert_eq!(cfg.dominator(3), Some(1));
+        assert!(cfg.dominates(0, 3));
+        assert!(!cfg.dominates(2, 3));
+        assert!(!cfg.is_reachable(4));
+    }
+}
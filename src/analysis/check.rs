@@ -0,0 +1,535 @@
+use std::{collections::VecDeque, ops::Range};
+
+use super::{
+    cfg::{next_ops, Cfg},
+    liveness::{Effects, Liveness},
+};
+use crate::{
+    common::*,
+    executor::{debugger::Location, Function},
+};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DiagnosticKind {
+    /// The execution may run past the end of the function or jump out of it.
+    MissingEnd,
+    /// Operations starting here are never executed.
+    Unreachable,
+    /// The called function does not exist.
+    UnknownFunction(UWord),
+    /// Parameters do not fit in the frame of the called function.
+    TooManyParameters {
+        function: UWord,
+        size: UWord,
+        frame_size: UWord,
+    },
+    /// `par` or `clf` without `app`, `app` before the previous call, or the end with a prepared call.
+    UnbalancedCall,
+    /// Paths reach the operation with different calls prepared.
+    InconsistentCall,
+    /// Local bytes may be read before they are written.
+    UninitializedLocal(Range<UWord>),
+}
+
+impl std::fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use DiagnosticKind::*;
+
+        match self {
+            MissingEnd => write!(f, "the execution may run out of the function"),
+            Unreachable => write!(f, "unreachable operations"),
+            UnknownFunction(id) => write!(f, "unknown function {}", id),
+            TooManyParameters {
+                function,
+                size,
+                frame_size,
+            } => write!(
+                f,
+                "{} bytes of parameters for function {} with the frame of {} bytes",
+                size, function, frame_size
+            ),
+            UnbalancedCall => write!(f, "unbalanced call"),
+            InconsistentCall => write!(f, "paths prepare different calls"),
+            UninitializedLocal(range) => write!(
+                f,
+                "locals {}..{} may be read before initialization",
+                range.start, range.end
+            ),
+        }
+    }
+}
+
+/// A problem found by [`check`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub location: Location,
+    pub kind: DiagnosticKind,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.location.function, self.location.op, self.kind
+        )
+    }
+}
+
+/// State of the call preparation before an operation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Call {
+    Idle,
+    /// A computed call has the unknown function.
+    Prepared {
+        function: Option<UWord>,
+        params: UWord,
+    },
+}
+
+/// Number of successors the operation has when all of them are in the program.
+fn expected_successors(op: &Op) -> usize {
+    match op {
+        Op::End(_) | Op::Ret(..) => 0,
+        Op::Go(Operand::Val(_)) | Op::Jmp(Operand::Val(_)) => 1,
+        Op::Go(_) | Op::Jmp(_) => 0,
+        op if op.is_conditional() => 2,
+        _ => 1,
+    }
+}
+
+struct Checker<'a, 'f> {
+    functions: &'a [Function<'f>],
+    diagnostics: Vec<Diagnostic>,
+    /// The most bytes of parameters each function is called with,
+    /// `None` when the function has no known call, such as the entry one.
+    params: Vec<Option<UWord>>,
+    /// Some call has a computed function, so the parameters of any function are unknown.
+    computed_call: bool,
+}
+
+impl Checker<'_, '_> {
+    fn report(&mut self, function: UWord, op: usize, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic {
+            location: Location {
+                function,
+                op: op as UWord,
+            },
+            kind,
+        });
+    }
+
+    fn check_flow(&mut self, id: UWord, program: &[Op]) {
+        if program.is_empty() {
+            self.report(id, 0, DiagnosticKind::MissingEnd);
+            return;
+        }
+
+        // Targets of computed jumps are unknown, so any operation may be reachable
+        let computed_jump = program.iter().any(|op| match op {
+            Op::Go(x) | Op::Jmp(x) => !matches!(x, Operand::Val(_)),
+            _ => false,
+        });
+
+        let cfg = Cfg::build(program);
+        for block in cfg.blocks().iter().filter(|_| !computed_jump) {
+            let reachable = cfg
+                .block_of(block.start)
+                .is_some_and(|b| cfg.is_reachable(b));
+
+            if !reachable {
+                self.report(id, block.start, DiagnosticKind::Unreachable);
+            }
+        }
+
+        let mut states = vec![None; program.len()];
+        let mut conflicts = vec![false; program.len()];
+        let mut queue = VecDeque::from([(0, Call::Idle)]);
+        while let Some((idx, call)) = queue.pop_front() {
+            match states[idx] {
+                Some(state) if state == call => continue,
+                Some(_) => {
+                    if !conflicts[idx] {
+                        conflicts[idx] = true;
+                        self.report(id, idx, DiagnosticKind::InconsistentCall);
+                    }
+
+                    continue;
+                }
+                None => states[idx] = Some(call),
+            }
+
+            let op = &program[idx];
+            let after = self.step(id, idx, op, call);
+            let next = next_ops(program, idx);
+            if next.len() < expected_successors(op) {
+                self.report(id, idx, DiagnosticKind::MissingEnd);
+            }
+
+            queue.extend(next.into_iter().map(|next| (next, after)));
+        }
+    }
+
+    fn step(&mut self, id: UWord, idx: usize, op: &Op, call: Call) -> Call {
+        match (op, call) {
+            (Op::App(x), Call::Idle) => {
+                let function = match *x {
+                    Operand::Val(callee) => {
+                        if callee as usize >= self.functions.len() {
+                            self.report(id, idx, DiagnosticKind::UnknownFunction(callee));
+                        }
+
+                        Some(callee)
+                    }
+                    _ => {
+                        self.computed_call = true;
+                        None
+                    }
+                };
+
+                Call::Prepared {
+                    function,
+                    params: 0,
+                }
+            }
+            (Op::Par(_, ot), Call::Prepared { function, params }) => Call::Prepared {
+                function,
+                params: params.wrapping_add(ot.size()),
+            },
+            (Op::Clf(_), Call::Prepared { function, params }) => {
+                let frame_size = function
+                    .and_then(|f| self.functions.get(f as usize))
+                    .map(Function::frame_size);
+
+                if let (Some(function), Some(frame_size)) = (function, frame_size) {
+                    if params > frame_size {
+                        self.report(
+                            id,
+                            idx,
+                            DiagnosticKind::TooManyParameters {
+                                function,
+                                size: params,
+                                frame_size,
+                            },
+                        );
+                    }
+
+                    let known = &mut self.params[function as usize];
+                    *known = Some(known.map_or(params, |known| known.max(params)));
+                }
+
+                Call::Idle
+            }
+            (Op::App(_), Call::Prepared { .. })
+            | (Op::Par(..), Call::Idle)
+            | (Op::Clf(_), Call::Idle)
+            | (Op::End(_), Call::Prepared { .. })
+            | (Op::Ret(..), Call::Prepared { .. }) => {
+                self.report(id, idx, DiagnosticKind::UnbalancedCall);
+                call
+            }
+            _ => call,
+        }
+    }
+
+    fn check_locals(&mut self, id: UWord, function: &Function) {
+        let program = function.program();
+        let params = match self.params[id as usize] {
+            Some(params) if !self.computed_call => params,
+            _ => return,
+        };
+
+        // Locals accessed with an offset or by a call can't be told apart
+        #[allow(clippy::unnecessary_map_or)]
+        let opaque = program
+            .iter()
+            .any(|op| Effects::of(op).map_or(true, |ef| ef.indexed || ef.uses_all));
+
+        if opaque {
+            return;
+        }
+
+        let liveness = match Liveness::analyze(program) {
+            Some(liveness) => liveness,
+            None => return,
+        };
+
+        let mut uninit: Option<Range<UWord>> = None;
+        for loc in params..function.frame_size() {
+            if liveness.is_live_at_entry(loc) {
+                match &mut uninit {
+                    Some(range) if range.end == loc => range.end = loc + 1,
+                    _ => {
+                        if let Some(range) = uninit.replace(loc..loc + 1) {
+                            self.report(id, 0, DiagnosticKind::UninitializedLocal(range));
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(range) = uninit {
+            self.report(id, 0, DiagnosticKind::UninitializedLocal(range));
+        }
+    }
+}
+
+/// Checks the whole program beyond validation of single operations.
+///
+/// Every path of a function must reach `end` or `ret`, every call must be prepared by `app`,
+/// its parameters must fit in the frame of the called function and locals except the parameters
+/// must be written before they are read. Computed jumps and calls are not followed:
+/// reachability is not checked in functions with computed jumps.
+///
+/// Locals are checked only in functions called from the program, since the parameters
+/// passed by the host are unknown, and not at all when some call is computed.
+/// They are not checked in functions taking their addresses, calling other functions
+/// or accessing them with an offset either.
+///
+/// Diagnostics are sorted by the location.
+pub fn check(functions: &[Function]) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        functions,
+        diagnostics: vec![],
+        params: vec![None; functions.len()],
+        computed_call: false,
+    };
+
+    for (id, function) in functions.iter().enumerate() {
+        checker.check_flow(id as UWord, function.program());
+    }
+
+    // Parameters are known only after all calls are seen
+    for (id, function) in functions.iter().enumerate() {
+        checker.check_locals(id as UWord, function);
+    }
+
+    let mut diagnostics = checker.diagnostics;
+    diagnostics.sort_by_key(|d| (d.location.function, d.location.op));
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    const WORD: UWord = std::mem::size_of::<UWord>() as UWord;
+
+    fn at(function: UWord, op: UWord) -> Location {
+        Location { function, op }
+    }
+
+    #[test]
+    fn check_valid_program() {
+        let main = assemble(
+            r#"
+            set u8 loc(0) 2
+            app 1
+            par u8 loc(0)
+            clf ref(1)
+            end loc(1)
+            "#,
+        )
+        .unwrap();
+
+        let inc = assemble(
+            r#"
+            inc u8 loc(0)
+            ret u8 loc(0)
+            "#,
+        )
+        .unwrap();
+
+        let functions = [Function::new(2, &main), Function::new(1, &inc)];
+        assert_eq!(check(&functions), []);
+    }
+
+    #[test]
+    fn check_diagnostics() {
+        let main = assemble(
+            r#"
+            inc u8 loc(1)
+            ifl u8 loc(1) 3
+            jmp skip
+            app 1
+            par u16 0
+            clf ref(0)
+            skip:
+            app 4
+            end 0
+            end 1
+            "#,
+        )
+        .unwrap();
+
+        let callee = assemble("ret u8 loc(0)").unwrap();
+        let branchy = assemble(
+            r#"
+            ift u8 1
+            app 1
+            end 0
+            "#,
+        )
+        .unwrap();
+
+        let falling = assemble("inc u8 loc(0)").unwrap();
+
+        let functions = [
+            Function::new(2, &main),
+            Function::new(1, &callee),
+            Function::new(0, &branchy),
+            Function::new(1, &falling),
+        ];
+
+        let diagnostics = check(&functions);
+        let kinds: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.location, d.kind.clone()))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                (
+                    at(0, 5),
+                    DiagnosticKind::TooManyParameters {
+                        function: 1,
+                        size: 2,
+                        frame_size: 1,
+                    },
+                ),
+                (at(0, 6), DiagnosticKind::UnknownFunction(4)),
+                (at(0, 7), DiagnosticKind::UnbalancedCall),
+                (at(0, 8), DiagnosticKind::Unreachable),
+                (at(2, 2), DiagnosticKind::InconsistentCall),
+                (at(3, 0), DiagnosticKind::MissingEnd),
+            ]
+        );
+
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "0:5: 2 bytes of parameters for function 1 with the frame of 1 bytes"
+        );
+    }
+
+    #[test]
+    fn check_uninitialized_locals() {
+        let main = assemble(
+            r#"
+            app 1
+            par u8 1
+            clf 0
+            end 0
+            "#,
+        )
+        .unwrap();
+
+        let add = assemble(
+            r#"
+            add u8 loc(1) loc(0)
+            ret u8 loc(1)
+            "#,
+        )
+        .unwrap();
+
+        // Parameters of a function without calls are unknown
+        let uncalled = assemble("end loc(0)").unwrap();
+
+        let functions = [
+            Function::new(0, &main),
+            Function::new(2, &add),
+            Function::new(1, &uncalled),
+        ];
+
+        assert_eq!(
+            check(&functions),
+            [Diagnostic {
+                location: at(1, 0),
+                kind: DiagnosticKind::UninitializedLocal(1..2),
+            }]
+        );
+    }
+
+    #[test]
+    fn check_locals_after_call() {
+        let main = assemble(
+            r#"
+            app 1
+            clf 0
+            end 0
+            "#,
+        )
+        .unwrap();
+
+        let caller = assemble(
+            r#"
+            app 2
+            clf 0
+            set u8 loc(0) 5
+            ret u8 loc(0)
+            "#,
+        )
+        .unwrap();
+
+        let callee = assemble("ret u8 0").unwrap();
+
+        let functions = [
+            Function::new(0, &main),
+            Function::new(1, &caller),
+            Function::new(0, &callee),
+        ];
+
+        assert_eq!(check(&functions), []);
+    }
+
+    #[test]
+    fn check_computed_jump() {
+        let program = assemble(
+            r#"
+            set uw loc(0) 2
+            go loc(0)
+            end 0
+            "#,
+        )
+        .unwrap();
+
+        let functions = [Function::new(WORD, &program)];
+        assert_eq!(check(&functions), []);
+    }
+
+    #[test]
+    fn check_conflicting_calls_once() {
+        let main = assemble(
+            r#"
+            ifl u8 loc(0) 1
+            jmp join
+            app 1
+            ifl u8 loc(0) 2
+            jmp join
+            par u8 0
+            join:
+            clf 0
+            end 0
+            "#,
+        )
+        .unwrap();
+
+        let callee = assemble("ret u8 0").unwrap();
+        let functions = [Function::new(1, &main), Function::new(1, &callee)];
+
+        assert_eq!(
+            check(&functions),
+            [
+                Diagnostic {
+                    location: at(0, 6),
+                    kind: DiagnosticKind::UnbalancedCall,
+                },
+                Diagnostic {
+                    location: at(0, 6),
+                    kind: DiagnosticKind::InconsistentCall,
+                },
+            ]
+        );
+    }
+}
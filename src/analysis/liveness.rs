@@ -0,0 +1,466 @@
+use std::ops::Range;
+
+use super::cfg::{next_ops, Cfg};
+use crate::common::*;
+
+const WORD: UWord = std::mem::size_of::<UWord>() as UWord;
+
+/// Splits a binary operation into its operands with their offsets.
+fn split(bin: BinOp) -> (UnOp, UnOp) {
+    match bin {
+        BinOp::None { x, y } => (UnOp::new(x), UnOp::new(y)),
+        BinOp::First { x, y, offset } => (UnOp::new(x).with_first(offset), UnOp::new(y)),
+        BinOp::Second { x, y, offset } => (UnOp::new(x), UnOp::new(y).with_first(offset)),
+        BinOp::Both { x, y, offset } => (
+            UnOp::new(x).with_first(offset),
+            UnOp::new(y).with_first(offset),
+        ),
+    }
+}
+
+/// Locals read and written by an operation.
+#[derive(Debug, Default)]
+pub(super) struct Effects {
+    pub(super) uses: Vec<Range<UWord>>,
+    pub(super) defs: Vec<Range<UWord>>,
+    pub(super) uses_all: bool,
+    /// A local is accessed with an offset.
+    pub(super) indexed: bool,
+}
+
+impl Effects {
+    /// Returns `None` if the operand may access the frame through a pointer.
+    fn read(&mut self, operand: Operand, size: UWord) -> Option<()> {
+        match operand {
+            Operand::Loc(loc) => self.uses.push(loc..loc.checked_add(size)?),
+            Operand::Ind(_) | Operand::Ref(_) | Operand::Glb(_) => return None,
+            Operand::Ret(_) | Operand::Val(_) | Operand::Emp => (),
+        }
+
+        Some(())
+    }
+
+    fn write(&mut self, operand: Operand, size: UWord) -> Option<()> {
+        match operand {
+            Operand::Loc(loc) => self.defs.push(loc..loc.checked_add(size)?),
+            Operand::Ind(_) | Operand::Ref(_) | Operand::Glb(_) => return None,
+            Operand::Ret(_) | Operand::Val(_) | Operand::Emp => (),
+        }
+
+        Some(())
+    }
+
+    /// Accesses an operand with an offset, so the address of a local is unknown.
+    fn access_offset(&mut self, x: Operand, offset: Operand, read: bool) -> Option<()> {
+        self.read(offset, WORD)?;
+        match x {
+            Operand::Loc(_) => {
+                self.uses_all |= read;
+                self.indexed = true;
+            }
+            Operand::Ind(_) | Operand::Ref(_) | Operand::Glb(_) => return None,
+            Operand::Ret(_) | Operand::Val(_) | Operand::Emp => (),
+        }
+
+        Some(())
+    }
+
+    fn un(&mut self, un: UnOp, size: UWord, read: bool, write: bool) -> Option<()> {
+        match un {
+            UnOp::None { x } => {
+                if read {
+                    self.read(x, size)?;
+                }
+                if write {
+                    self.write(x, size)?;
+                }
+            }
+            UnOp::First { x, offset } => self.access_offset(x, offset, read)?,
+        }
+
+        Some(())
+    }
+
+    fn bin(&mut self, bin: BinOp, size: UWord, read_x: bool) -> Option<()> {
+        let (x, y, x_offset, y_offset) = match bin {
+            BinOp::None { x, y } => (x, y, None, None),
+            BinOp::First { x, y, offset } => (x, y, Some(offset), None),
+            BinOp::Second { x, y, offset } => (x, y, None, Some(offset)),
+            BinOp::Both { x, y, offset } => (x, y, Some(offset), Some(offset)),
+        };
+
+        match y_offset {
+            None => self.read(y, size)?,
+            Some(offset) => self.access_offset(y, offset, true)?,
+        }
+
+        match x_offset {
+            None => {
+                if read_x {
+                    self.read(x, size)?;
+                }
+                self.write(x, size)?;
+            }
+            Some(offset) => self.access_offset(x, offset, read_x)?,
+        }
+
+        Some(())
+    }
+
+    pub(super) fn of(op: &Op) -> Option<Self> {
+        use Op::*;
+
+        let mut ef = Self::default();
+        match *op {
+            Nop | Fls => (),
+            Set(bin, ot) => ef.bin(bin, ot.size(), false)?,
+            Add(bin, ot)
+            | Sub(bin, ot)
+            | Mul(bin, ot)
+            | Div(bin, ot)
+            | Mod(bin, ot)
+            | And(bin, ot)
+            | Or(bin, ot)
+            | Xor(bin, ot)
+            | Min(bin, ot)
+            | Max(bin, ot) => ef.bin(bin, ot.size(), true)?,
+            Ife(bin, ot)
+            | Ifl(bin, ot)
+            | Ifg(bin, ot)
+            | Ine(bin, ot)
+            | Inl(bin, ot)
+            | Ing(bin, ot)
+            | Ifa(bin, ot)
+            | Ifo(bin, ot)
+            | Ifx(bin, ot)
+            | Ina(bin, ot)
+            | Ino(bin, ot)
+            | Inx(bin, ot) => {
+                let (x, y) = split(bin);
+                ef.un(x, ot.size(), true, false)?;
+                ef.un(y, ot.size(), true, false)?;
+            }
+            Cnv(x, y, from, to) => {
+                ef.read(y, from.size())?;
+                ef.write(x, to.size())?;
+            }
+            Shl(x, y, ot) | Shr(x, y, ot) => {
+                ef.read(y, 1)?;
+                ef.read(x, ot.size())?;
+                ef.write(x, ot.size())?;
+            }
+            Not(un, ot) | Neg(un, ot) | Inc(un, ot) | Dec(un, ot) | Sqt(un, ot) | Abs(un, ot) => {
+                ef.un(un, ot.size(), true, true)?
+            }
+            Ift(un, ot) | Iff(un, ot) | Par(un, ot) | Ret(un, ot) => {
+                ef.un(un, ot.size(), true, false)?
+            }
+            Out(un) => ef.un(un, 1, true, false)?,
+            In(bin) => {
+                let (x, y) = split(bin);
+                ef.un(x, 1, false, true)?;
+                ef.un(y, 1, false, true)?;
+            }
+            End(x) | Slp(x) | App(x) | Sfd(x) => ef.read(x, WORD)?,
+            Gfd(x) => ef.write(x, WORD)?,
+            Go(x) | Jmp(x) => {
+                ef.read(x, WORD)?;
+
+                // The target is unknown
+                ef.uses_all |= !matches!(x, Operand::Val(_));
+            }
+            Clf(x) => {
+                ef.read(x, WORD)?;
+
+                // The called function may access the frame
+                ef.uses_all = true;
+            }
+            Zer(..) | Cmp(..) | Cpy(..) => return None,
+        }
+
+        Some(ef)
+    }
+}
+
+/// Live locals after each operation.
+///
+/// Locals are tracked by bytes of the frame.
+#[derive(Debug)]
+pub struct Liveness {
+    live_in: Vec<bool>,
+    live_out: Vec<Vec<bool>>,
+}
+
+impl Liveness {
+    /// Analyzes the program.
+    ///
+    /// Returns `None` if the program accesses memory through pointers, so locals may be aliased.
+    pub fn analyze(program: &[Op]) -> Option<Self> {
+        let effects = program
+            .iter()
+            .map(Effects::of)
+            .collect::<Option<Vec<_>>>()?;
+
+        let frame_size = effects
+            .iter()
+            .flat_map(|ef| ef.uses.iter().chain(&ef.defs))
+            .map(|r| r.end)
+            .max()
+            .unwrap_or(0) as usize;
+
+        let mut live_in = vec![vec![false; frame_size]; program.len()];
+        let mut live_out = vec![vec![false; frame_size]; program.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for idx in (0..program.len()).rev() {
+                let mut out = vec![false; frame_size];
+                for next in next_ops(program, idx) {
+                    for (o, &l) in out.iter_mut().zip(&live_in[next]) {
+                        *o |= l;
+                    }
+                }
+
+                let ef = &effects[idx];
+                let mut inp = out.clone();
+                if ef.uses_all {
+                    inp.iter_mut().for_each(|l| *l = true);
+                } else {
+                    for r in &ef.defs {
+                        inp[r.start as usize..r.end as usize]
+                            .iter_mut()
+                            .for_each(|l| *l = false);
+                    }
+
+                    for r in &ef.uses {
+                        inp[r.start as usize..r.end as usize]
+                            .iter_mut()
+                            .for_each(|l| *l = true);
+                    }
+                }
+
+                if inp != live_in[idx] || out != live_out[idx] {
+                    live_in[idx] = inp;
+                    live_out[idx] = out;
+                    changed = true;
+                }
+            }
+        }
+
+        Some(Self {
+            live_in: live_in.into_iter().next().unwrap_or_default(),
+            live_out,
+        })
+    }
+
+    /// Checks whether the local byte may be read before it is written, such as a parameter.
+    pub fn is_live_at_entry(&self, loc: UWord) -> bool {
+        self.live_in.get(loc as usize).copied().unwrap_or(false)
+    }
+
+    /// Checks whether the local byte may be read after the operation.
+    pub fn is_live(&self, idx: usize, loc: UWord) -> bool {
+        self.live_out[idx]
+            .get(loc as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// Checks whether the operation only writes its result, so it can be removed when the result is dead.
+fn is_pure(op: &Op) -> bool {
+    use Op::*;
+
+    match op {
+        Set(..) | Cnv(..) | Add(..) | Sub(..) | Mul(..) | Neg(..) | Inc(..) | Dec(..) => true,
+        Abs(..) | Min(..) | Max(..) => true,
+        Sqt(_, ot) => ot.is_float(),
+        And(_, ot) | Or(_, ot) | Xor(_, ot) | Not(_, ot) | Shl(.., ot) | Shr(.., ot) => {
+            !ot.is_float()
+        }
+        _ => false,
+    }
+}
+
+/// Replaces writes to dead locals with `nop`.
+fn eliminate_dead_stores(program: &mut [Op]) {
+    loop {
+        let liveness = match Liveness::analyze(program) {
+            Some(liveness) => liveness,
+            None => return,
+        };
+
+        let mut changed = false;
+        for (idx, op) in program.iter_mut().enumerate() {
+            if !is_pure(op) {
+                continue;
+            }
+
+            let ef = match Effects::of(op) {
+                Some(ef) => ef,
+                None => continue,
+            };
+
+            let dead = !ef.defs.is_empty()
+                && ef
+                    .defs
+                    .iter()
+                    .all(|r| r.clone().all(|loc| !liveness.is_live(idx, loc)));
+
+            if dead {
+                *op = Op::Nop;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return;
+        }
+    }
+}
+
+/// Removes unreachable operations and `nop`s, and fixes up jump targets.
+fn compact(program: Vec<Op>) -> Vec<Op> {
+    // Computed targets can't be fixed up
+    let computed = program
+        .iter()
+        .any(|op| matches!(op, Op::Go(x) | Op::Jmp(x) if !matches!(x, Operand::Val(_))));
+
+    if computed {
+        return program;
+    }
+
+    let cfg = Cfg::build(&program);
+    let keep: Vec<bool> = (0..program.len())
+        .map(|idx| {
+            let reachable = cfg.block_of(idx).is_some_and(|b| cfg.is_reachable(b));
+
+            // A `nop` after a condition is skipped by it, so it stays
+            let guarded = idx > 0 && program[idx - 1].is_conditional();
+            reachable && (program[idx] != Op::Nop || guarded)
+        })
+        .collect();
+
+    let mut new_idx = Vec::with_capacity(program.len());
+    let mut len = 0;
+    for &k in &keep {
+        new_idx.push(len);
+        len += k as usize;
+    }
+
+    let removed = program.len() - len;
+    let remap = |target: isize| -> isize {
+        if target < 0 {
+            target
+        } else if target as usize >= program.len() {
+            target - removed as isize
+        } else {
+            new_idx[target as usize] as isize
+        }
+    };
+
+    program
+        .iter()
+        .enumerate()
+        .filter(|&(idx, _)| keep[idx])
+        .map(|(idx, &op)| match op {
+            Op::Go(Operand::Val(target)) => Op::Go(Operand::Val(remap(target as isize) as UWord)),
+            Op::Jmp(Operand::Val(offset)) => {
+                let target = remap(idx as isize + offset as IWord as isize);
+                let offset = target - new_idx[idx] as isize;
+                Op::Jmp(Operand::Val(offset as IWord as UWord))
+            }
+            op => op,
+        })
+        .collect()
+}
+
+/// Removes writes to dead locals and unreachable operations.
+///
+/// Locals are dead after `end` and `ret`. Writes are kept if the program accesses memory through pointers.
+pub fn eliminate_dead_code(program: &[Op]) -> Vec<Op> {
+    let mut program = program.to_vec();
+    eliminate_dead_stores(&mut program);
+    compact(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    #[test]
+    fn liveness() {
+        let program = assemble(
+            r#"
+            set u8 loc(0) 1
+            set u16 loc(1) 2
+            add u8 loc(0) loc(1)
+            ret u8 loc(0)
+            "#,
+        )
+        .unwrap();
+
+        let liveness = Liveness::analyze(&program).unwrap();
+        assert!(liveness.is_live(0, 0));
+        assert!(liveness.is_live(1, 1));
+        assert!(!liveness.is_live(1, 2));
+        assert!(!liveness.is_live(2, 1));
+        assert!(liveness.is_live(2, 0));
+        assert!(!liveness.is_live(3, 0));
+
+        let program =
+            assemble("set u32 loc(4) 1\ncnv u32 u8 loc(0) loc(4)\nret u8 loc(0)").unwrap();
+        let liveness = Liveness::analyze(&program).unwrap();
+        assert!(liveness.is_live(0, 7));
+        assert!(!liveness.is_live(1, 7));
+
+        let program = assemble("set uw loc(0) ref(0)\nend 0").unwrap();
+        assert!(Liveness::analyze(&program).is_none());
+    }
+
+    #[test]
+    fn dead_code() {
+        let program = assemble(
+            r#"
+            set u8 loc(0) 0
+            set u8 loc(1) 7
+            set u8 loc(2) 7
+            loop:
+            inc u8 loc(0)
+            ifl u8 loc(0) 3
+            jmp loop
+            go exit
+            set u8 loc(1) 1
+            exit:
+            set u8 loc(3) 1
+            ift u8 loc(2)
+            set u8 loc(3) 2
+            add u8 loc(0) loc(1)
+            ret u8 loc(0)
+            "#,
+        )
+        .unwrap();
+
+        let expected = assemble(
+            r#"
+            set u8 loc(0) 0
+            set u8 loc(1) 7
+            set u8 loc(2) 7
+            loop:
+            inc u8 loc(0)
+            ifl u8 loc(0) 3
+            jmp loop
+            go exit
+            exit:
+            ift u8 loc(2)
+            nop
+            add u8 loc(0) loc(1)
+            ret u8 loc(0)
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(eliminate_dead_code(&program), expected);
+    }
+}
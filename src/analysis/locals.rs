@@ -0,0 +1,331 @@
+use std::ops::Range;
+
+use super::liveness::{Effects, Liveness};
+use crate::common::*;
+
+/// New place of a local after compaction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LocalMapping {
+    /// Bytes of the frame the local took.
+    pub old: Range<UWord>,
+    /// New start of the local.
+    pub new: UWord,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompactLocals {
+    pub program: Vec<Op>,
+    pub frame_size: UWord,
+    pub mapping: Vec<LocalMapping>,
+}
+
+fn map_un<F>(un: UnOp, f: &F) -> UnOp
+where
+    F: Fn(Operand) -> Operand,
+{
+    match un {
+        UnOp::None { x } => UnOp::new(f(x)),
+        UnOp::First { x, offset } => UnOp::new(f(x)).with_first(f(offset)),
+    }
+}
+
+fn map_bin<F>(bin: BinOp, f: &F) -> BinOp
+where
+    F: Fn(Operand) -> Operand,
+{
+    match bin {
+        BinOp::None { x, y } => BinOp::new(f(x), f(y)),
+        BinOp::First { x, y, offset } => BinOp::new(f(x), f(y)).with_first(f(offset)),
+        BinOp::Second { x, y, offset } => BinOp::new(f(x), f(y)).with_second(f(offset)),
+        BinOp::Both { x, y, offset } => BinOp::new(f(x), f(y)).with_both(f(offset)),
+    }
+}
+
+fn map_operands<F>(op: Op, f: F) -> Op
+where
+    F: Fn(Operand) -> Operand,
+{
+    use Op::*;
+
+    match op {
+        Nop | Fls => op,
+        End(x) => End(f(x)),
+        Slp(x) => Slp(f(x)),
+        Go(x) => Go(f(x)),
+        Jmp(x) => Jmp(f(x)),
+        App(x) => App(f(x)),
+        Clf(x) => Clf(f(x)),
+        Sfd(x) => Sfd(f(x)),
+        Gfd(x) => Gfd(f(x)),
+        Cnv(x, y, t, u) => Cnv(f(x), f(y), t, u),
+        Shl(x, y, t) => Shl(f(x), f(y), t),
+        Shr(x, y, t) => Shr(f(x), f(y), t),
+        Set(b, t) => Set(map_bin(b, &f), t),
+        Add(b, t) => Add(map_bin(b, &f), t),
+        Sub(b, t) => Sub(map_bin(b, &f), t),
+        Mul(b, t) => Mul(map_bin(b, &f), t),
+        Div(b, t) => Div(map_bin(b, &f), t),
+        Mod(b, t) => Mod(map_bin(b, &f), t),
+        And(b, t) => And(map_bin(b, &f), t),
+        Or(b, t) => Or(map_bin(b, &f), t),
+        Xor(b, t) => Xor(map_bin(b, &f), t),
+        Min(b, t) => Min(map_bin(b, &f), t),
+        Max(b, t) => Max(map_bin(b, &f), t),
+        Ife(b, t) => Ife(map_bin(b, &f), t),
+        Ifl(b, t) => Ifl(map_bin(b, &f), t),
+        Ifg(b, t) => Ifg(map_bin(b, &f), t),
+        Ine(b, t) => Ine(map_bin(b, &f), t),
+        Inl(b, t) => Inl(map_bin(b, &f), t),
+        Ing(b, t) => Ing(map_bin(b, &f), t),
+        Ifa(b, t) => Ifa(map_bin(b, &f), t),
+        Ifo(b, t) => Ifo(map_bin(b, &f), t),
+        Ifx(b, t) => Ifx(map_bin(b, &f), t),
+        Ina(b, t) => Ina(map_bin(b, &f), t),
+        Ino(b, t) => Ino(map_bin(b, &f), t),
+        Inx(b, t) => Inx(map_bin(b, &f), t),
+        In(b) => In(map_bin(b, &f)),
+        Not(u, t) => Not(map_un(u, &f), t),
+        Neg(u, t) => Neg(map_un(u, &f), t),
+        Inc(u, t) => Inc(map_un(u, &f), t),
+        Dec(u, t) => Dec(map_un(u, &f), t),
+        Sqt(u, t) => Sqt(map_un(u, &f), t),
+        Abs(u, t) => Abs(map_un(u, &f), t),
+        Ift(u, t) => Ift(map_un(u, &f), t),
+        Iff(u, t) => Iff(map_un(u, &f), t),
+        Par(u, t) => Par(map_un(u, &f), t),
+        Ret(u, t) => Ret(map_un(u, &f), t),
+        Out(u) => Out(map_un(u, &f)),
+        Zer(x, y) => Zer(f(x), f(y)),
+        Cmp(x, y, z) => Cmp(f(x), f(y), f(z)),
+        Cpy(x, y, z) => Cpy(f(x), f(y), f(z)),
+    }
+}
+
+fn overlaps(a: &Range<UWord>, b: &Range<UWord>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Natural alignment of a local, up to 8 bytes.
+fn align_of(local: &Range<UWord>) -> UWord {
+    let size = local.end - local.start;
+    let mut align = 1;
+    while align < 8 && align * 2 <= size {
+        align *= 2;
+    }
+
+    align
+}
+
+/// Renumbers locals so that locals which are never live at the same time share the frame.
+///
+/// A local is a run of frame bytes accessed together. Locals intersecting the first `params` bytes
+/// and locals read before they are written keep their place.
+///
+/// Returns `None` if the program accesses the frame through pointers or offsets, or calls a function,
+/// so addresses of locals may be observed.
+pub fn compact_locals(program: &[Op], params: UWord) -> Option<CompactLocals> {
+    let effects = program
+        .iter()
+        .map(|op| match (op, Effects::of(op)?) {
+            (Op::Clf(_), _) => None,
+            (_, ef) if ef.indexed => None,
+            (_, ef) => Some(ef),
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let liveness = Liveness::analyze(program)?;
+
+    // Merge overlapping accesses into locals
+    let mut ranges: Vec<_> = effects
+        .iter()
+        .flat_map(|ef| ef.uses.iter().chain(&ef.defs).cloned())
+        .collect();
+    ranges.sort_by_key(|r| (r.start, r.end));
+
+    let mut locals: Vec<Range<UWord>> = vec![];
+    for r in ranges {
+        match locals.last_mut() {
+            Some(last) if r.start < last.end => last.end = last.end.max(r.end),
+            _ => locals.push(r),
+        }
+    }
+
+    let touches =
+        |local: &Range<UWord>, ranges: &[Range<UWord>]| ranges.iter().any(|r| overlaps(local, r));
+
+    let is_live =
+        |idx: usize, local: &Range<UWord>| local.clone().any(|loc| liveness.is_live(idx, loc));
+
+    let mut interferes = vec![vec![false; locals.len()]; locals.len()];
+    for (idx, ef) in effects.iter().enumerate() {
+        let defined: Vec<_> = (0..locals.len())
+            .filter(|&l| touches(&locals[l], &ef.defs))
+            .collect();
+
+        let live: Vec<_> = (0..locals.len())
+            .filter(|&l| is_live(idx, &locals[l]))
+            .collect();
+
+        for &d in &defined {
+            for &l in live.iter().chain(&defined) {
+                if d != l {
+                    interferes[d][l] = true;
+                    interferes[l][d] = true;
+                }
+            }
+        }
+    }
+
+    let pinned: Vec<bool> = locals
+        .iter()
+        .map(|local| {
+            local.start < params || local.clone().any(|loc| liveness.is_live_at_entry(loc))
+        })
+        .collect();
+
+    let mut placed: Vec<Option<UWord>> = locals
+        .iter()
+        .zip(&pinned)
+        .map(|(local, &pin)| if pin { Some(local.start) } else { None })
+        .collect();
+
+    for l in 0..locals.len() {
+        if placed[l].is_some() {
+            continue;
+        }
+
+        let size = locals[l].end - locals[l].start;
+        let align = align_of(&locals[l]);
+        let others: Vec<Range<UWord>> = (0..locals.len())
+            .filter(|&o| interferes[l][o])
+            .filter_map(|o| placed[o].map(|start| start..start + locals[o].end - locals[o].start))
+            .collect();
+
+        let mut start = 0;
+        while let Some(other) = others.iter().find(|o| overlaps(o, &(start..start + size))) {
+            start = other.end.div_ceil(align) * align;
+        }
+
+        placed[l] = Some(start);
+    }
+
+    let mapping: Vec<LocalMapping> = locals
+        .iter()
+        .zip(&placed)
+        .map(|(local, start)| LocalMapping {
+            old: local.clone(),
+            new: start.unwrap(),
+        })
+        .collect();
+
+    let new_loc = |loc: UWord| {
+        mapping
+            .iter()
+            .find(|m| m.old.contains(&loc))
+            .map_or(loc, |m| m.new + (loc - m.old.start))
+    };
+
+    let program = program
+        .iter()
+        .map(|&op| {
+            map_operands(op, |x| match x {
+                Operand::Loc(loc) => Operand::Loc(new_loc(loc)),
+                x => x,
+            })
+        })
+        .collect();
+
+    let frame_size = mapping
+        .iter()
+        .map(|m| m.new + m.old.end - m.old.start)
+        .max()
+        .unwrap_or(0)
+        .max(params);
+
+    Some(CompactLocals {
+        program,
+        frame_size,
+        mapping,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assembler::assemble,
+        executor::{ExecutionSuccess, Executor, Function},
+    };
+
+    const WORD: UWord = std::mem::size_of::<UWord>() as UWord;
+
+    #[test]
+    fn compact_single_use_locals() {
+        let program = assemble(
+            r#"
+            set u32 loc(0) 5
+            add u32 loc(0) 1
+            set u32 loc(4) loc(0)
+            mul u32 loc(4) 7
+            cnv u32 uw loc(8) loc(4)
+            end loc(8)
+            "#,
+        )
+        .unwrap();
+
+        let compact = compact_locals(&program, 0).unwrap();
+        assert_eq!(compact.frame_size, WORD);
+        assert_eq!(
+            compact.mapping,
+            [
+                LocalMapping { old: 0..4, new: 0 },
+                LocalMapping { old: 4..8, new: 0 },
+                LocalMapping {
+                    old: 8..8 + WORD,
+                    new: 0
+                },
+            ]
+        );
+        assert_eq!(
+            compact.program[4],
+            Op::Cnv(Operand::Loc(0), Operand::Loc(0), OpType::U32, OpType::Uw)
+        );
+
+        let functions = [Function::new(compact.frame_size, &compact.program)];
+        let mut exe = Executor::new(&functions);
+        exe.call(0, 0).unwrap();
+        assert_eq!(exe.run(), Ok(ExecutionSuccess::End(42)));
+    }
+
+    #[test]
+    fn compact_keeps_params() {
+        let program = assemble(
+            r#"
+            set u32 loc(8) 2
+            set u32 loc(12) loc(0)
+            add u32 loc(12) loc(8)
+            set u32 ^0 loc(12)
+            ret u8 emp
+            "#,
+        )
+        .unwrap();
+
+        let compact = compact_locals(&program, 4).unwrap();
+        assert_eq!(
+            compact.mapping,
+            [
+                LocalMapping { old: 0..4, new: 0 },
+                LocalMapping { old: 8..12, new: 4 },
+                LocalMapping {
+                    old: 12..16,
+                    new: 0
+                },
+            ]
+        );
+        assert_eq!(compact.frame_size, 8);
+
+        assert!(compact_locals(&assemble("set uw loc(0) ref(4)\nend 0").unwrap(), 0).is_none());
+        assert!(compact_locals(&assemble("set u8 loc(0){loc(4)} 1\nend 0").unwrap(), 0).is_none());
+        assert!(compact_locals(&assemble("app 0\nclf 0\nend 0").unwrap(), 0).is_none());
+    }
+}
@@ -0,0 +1,9 @@
+mod cfg;
+mod check;
+mod liveness;
+mod locals;
+
+pub use cfg::*;
+pub use check::*;
+pub use liveness::*;
+pub use locals::*;
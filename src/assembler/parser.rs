@@ -0,0 +1,606 @@
+use pest::{
+    error::{Error, LineColLocation},
+    iterators::Pair,
+    Parser,
+};
+
+use crate::common::*;
+use std::collections::HashMap;
+
+#[derive(Parser)]
+#[grammar = "./assembler/syntax.pest"]
+struct AsmParser;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AssembleErrorKind {
+    Syntax,
+    UnknownMnemonic,
+    WrongTypeCount { expected: usize, found: usize },
+    WrongOperandCount { expected: usize, found: usize },
+    UnexpectedOffset,
+    DifferentOffsets,
+    TooLargeValue,
+    UndefinedLabel,
+    DuplicateLabel,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AssembleError {
+    pub kind: AssembleErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for AssembleErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use AssembleErrorKind::*;
+
+        match self {
+            Syntax => write!(f, "syntax error"),
+            UnknownMnemonic => write!(f, "unknown mnemonic"),
+            WrongTypeCount { expected, found } => {
+                write!(f, "expected {} types, found {}", expected, found)
+            }
+            WrongOperandCount { expected, found } => {
+                write!(f, "expected {} operands, found {}", expected, found)
+            }
+            UnexpectedOffset => write!(f, "unexpected offset"),
+            DifferentOffsets => write!(f, "operands have different offsets"),
+            TooLargeValue => write!(f, "too large value"),
+            UndefinedLabel => write!(f, "undefined label"),
+            DuplicateLabel => write!(f, "duplicate label"),
+        }
+    }
+}
+
+impl std::fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+impl AssembleError {
+    fn new(kind: AssembleErrorKind, (line, column): (usize, usize)) -> Self {
+        Self { kind, line, column }
+    }
+}
+
+impl From<Error<Rule>> for AssembleError {
+    fn from(e: Error<Rule>) -> Self {
+        let pos = match e.line_col {
+            LineColLocation::Pos(pos) => pos,
+            LineColLocation::Span(pos, _) => pos,
+        };
+
+        AssembleError::new(AssembleErrorKind::Syntax, pos)
+    }
+}
+
+fn parse_int(s: &str) -> Option<UWord> {
+    if let Some(s) = s.strip_prefix('-') {
+        // Negative values are stored in two's complement
+        let val = parse_int(s)?;
+
+        return if val <= IWord::MIN.unsigned_abs() as UWord {
+            Some(val.wrapping_neg())
+        } else {
+            None
+        };
+    }
+
+    let (s, rad) = if let Some(s) = s.strip_prefix("0b") {
+        (s, 2)
+    } else if let Some(s) = s.strip_prefix("0o") {
+        (s, 8)
+    } else if let Some(s) = s.strip_prefix("0x") {
+        (s, 16)
+    } else {
+        (s, 10)
+    };
+
+    let s: String = s.chars().filter(|&c| c != '_').collect();
+    UWord::from_str_radix(&s, rad).ok()
+}
+
+fn parse_op_type(s: &str) -> OpType {
+    use OpType::*;
+
+    match s {
+        "u8" => U8,
+        "i8" => I8,
+        "u16" => U16,
+        "i16" => I16,
+        "u32" => U32,
+        "i32" => I32,
+        "u64" => U64,
+        "i64" => I64,
+        "uw" => Uw,
+        "iw" => Iw,
+        "f32" => F32,
+        "f64" => F64,
+        _ => unreachable!(),
+    }
+}
+
+type Labels<'i> = HashMap<&'i str, UWord>;
+
+fn parse_value(pair: Pair<Rule>, labels: &Labels) -> Result<Operand, AssembleError> {
+    let pos = pair.as_span().start_pos().line_col();
+    let value = pair.into_inner().next().unwrap();
+
+    let (kind, int) = match value.as_rule() {
+        Rule::emp => return Ok(Operand::Emp),
+        Rule::ident => {
+            return match labels.get(value.as_str()) {
+                Some(&idx) => Ok(Operand::Val(idx)),
+                None => Err(AssembleError::new(AssembleErrorKind::UndefinedLabel, pos)),
+            }
+        }
+        Rule::long => {
+            let mut inner = value.into_inner();
+            let kind = inner.next().unwrap().as_str();
+            (kind, inner.next().unwrap().as_str())
+        }
+        Rule::short => {
+            let mut inner = value.into_inner();
+            let first = inner.next().unwrap();
+
+            match first.as_rule() {
+                Rule::prefix => (first.as_str(), inner.next().unwrap().as_str()),
+                _ => ("", first.as_str()),
+            }
+        }
+        _ => unreachable!(),
+    };
+
+    let val =
+        parse_int(int).ok_or_else(|| AssembleError::new(AssembleErrorKind::TooLargeValue, pos))?;
+
+    Ok(match kind {
+        "loc" => Operand::Loc(val),
+        "ind" | "*" => Operand::Ind(val),
+        "ret" | "^" => Operand::Ret(val),
+        "val" | "" => Operand::Val(val),
+        "ref" | "&" => Operand::Ref(val),
+        "glb" | "." => Operand::Glb(val),
+        _ => unreachable!(),
+    })
+}
+
+struct Arg {
+    operand: Operand,
+    offset: Option<Operand>,
+    label: bool,
+    pos: (usize, usize),
+}
+
+fn parse_operand(pair: Pair<Rule>, labels: &Labels) -> Result<Arg, AssembleError> {
+    let pos = pair.as_span().start_pos().line_col();
+    let mut inner = pair.into_inner();
+    let value = inner.next().unwrap();
+    let label = value.clone().into_inner().next().unwrap().as_rule() == Rule::ident;
+    let operand = parse_value(value, labels)?;
+
+    let offset = match inner.next() {
+        Some(offset) => Some(parse_value(offset.into_inner().next().unwrap(), labels)?),
+        None => None,
+    };
+
+    Ok(Arg {
+        operand,
+        offset,
+        label,
+        pos,
+    })
+}
+
+struct Args {
+    types: Vec<OpType>,
+    operands: Vec<Arg>,
+    pos: (usize, usize),
+}
+
+impl Args {
+    fn expect(&self, types: usize, operands: usize) -> Result<(), AssembleError> {
+        if self.types.len() != types {
+            return Err(AssembleError::new(
+                AssembleErrorKind::WrongTypeCount {
+                    expected: types,
+                    found: self.types.len(),
+                },
+                self.pos,
+            ));
+        }
+
+        if self.operands.len() != operands {
+            return Err(AssembleError::new(
+                AssembleErrorKind::WrongOperandCount {
+                    expected: operands,
+                    found: self.operands.len(),
+                },
+                self.pos,
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn plain(&self, idx: usize) -> Result<Operand, AssembleError> {
+        let arg = &self.operands[idx];
+
+        match arg.offset {
+            Some(_) => Err(AssembleError::new(
+                AssembleErrorKind::UnexpectedOffset,
+                arg.pos,
+            )),
+            None => Ok(arg.operand),
+        }
+    }
+
+    fn un(&self) -> UnOp {
+        let x = &self.operands[0];
+        let un = UnOp::new(x.operand);
+
+        match x.offset {
+            Some(offset) => un.with_first(offset),
+            None => un,
+        }
+    }
+
+    fn bin(&self) -> Result<BinOp, AssembleError> {
+        let (x, y) = (&self.operands[0], &self.operands[1]);
+        let bin = BinOp::new(x.operand, y.operand);
+
+        Ok(match (x.offset, y.offset) {
+            (None, None) => bin,
+            (Some(q), None) => bin.with_first(q),
+            (None, Some(q)) => bin.with_second(q),
+            (Some(q), Some(w)) if q == w => bin.with_both(q),
+            (Some(_), Some(_)) => {
+                return Err(AssembleError::new(
+                    AssembleErrorKind::DifferentOffsets,
+                    y.pos,
+                ))
+            }
+        })
+    }
+
+    fn none(&self, op: Op) -> Result<Op, AssembleError> {
+        self.expect(0, 0)?;
+        Ok(op)
+    }
+
+    fn single(&self, op: fn(Operand) -> Op) -> Result<Op, AssembleError> {
+        self.expect(0, 1)?;
+        Ok(op(self.plain(0)?))
+    }
+
+    fn un_op(&self, op: fn(UnOp, OpType) -> Op) -> Result<Op, AssembleError> {
+        self.expect(1, 1)?;
+        Ok(op(self.un(), self.types[0]))
+    }
+
+    fn bin_op(&self, op: fn(BinOp, OpType) -> Op) -> Result<Op, AssembleError> {
+        self.expect(1, 2)?;
+        Ok(op(self.bin()?, self.types[0]))
+    }
+
+    fn shift(&self, op: fn(Operand, Operand, OpType) -> Op) -> Result<Op, AssembleError> {
+        self.expect(1, 2)?;
+        Ok(op(self.plain(0)?, self.plain(1)?, self.types[0]))
+    }
+
+    fn triple(&self, op: fn(Operand, Operand, Operand) -> Op) -> Result<Op, AssembleError> {
+        self.expect(0, 3)?;
+        Ok(op(self.plain(0)?, self.plain(1)?, self.plain(2)?))
+    }
+}
+
+fn assemble_op(pair: Pair<Rule>, idx: UWord, labels: &Labels) -> Result<Op, AssembleError> {
+    use Op::*;
+
+    let pos = pair.as_span().start_pos().line_col();
+    let mut inner = pair.into_inner();
+    let mnemonic = inner.next().unwrap().as_str();
+
+    let mut args = Args {
+        types: vec![],
+        operands: vec![],
+        pos,
+    };
+
+    for pair in inner {
+        match pair.as_rule() {
+            Rule::op_type => args.types.push(parse_op_type(pair.as_str())),
+            Rule::operand => args.operands.push(parse_operand(pair, labels)?),
+            _ => unreachable!(),
+        }
+    }
+
+    match mnemonic {
+        "nop" => args.none(Nop),
+        "end" => args.single(End),
+        "slp" => args.single(Slp),
+        "set" => args.bin_op(Set),
+        "cnv" => {
+            args.expect(2, 2)?;
+            Ok(Cnv(
+                args.plain(0)?,
+                args.plain(1)?,
+                args.types[0],
+                args.types[1],
+            ))
+        }
+        "add" => args.bin_op(Add),
+        "sub" => args.bin_op(Sub),
+        "mul" => args.bin_op(Mul),
+        "div" => args.bin_op(Div),
+        "mod" => args.bin_op(Mod),
+        "shl" => args.shift(Shl),
+        "shr" => args.shift(Shr),
+        "and" => args.bin_op(And),
+        "or" => args.bin_op(Or),
+        "xor" => args.bin_op(Xor),
+        "not" => args.un_op(Not),
+        "neg" => args.un_op(Neg),
+        "inc" => args.un_op(Inc),
+        "dec" => args.un_op(Dec),
+        "sqt" => args.un_op(Sqt),
+        "abs" => args.un_op(Abs),
+        "min" => args.bin_op(Min),
+        "max" => args.bin_op(Max),
+        "go" => args.single(Go),
+        "jmp" => {
+            args.expect(0, 1)?;
+            let x = args.plain(0)?;
+
+            // A label is jumped to relative to the current operation
+            Ok(Jmp(if args.operands[0].label {
+                x.map(|target| target.wrapping_sub(idx))
+            } else {
+                x
+            }))
+        }
+        "ift" => args.un_op(Ift),
+        "iff" => args.un_op(Iff),
+        "ife" => args.bin_op(Ife),
+        "ifl" => args.bin_op(Ifl),
+        "ifg" => args.bin_op(Ifg),
+        "ine" => args.bin_op(Ine),
+        "inl" => args.bin_op(Inl),
+        "ing" => args.bin_op(Ing),
+        "ifa" => args.bin_op(Ifa),
+        "ifo" => args.bin_op(Ifo),
+        "ifx" => args.bin_op(Ifx),
+        "ina" => args.bin_op(Ina),
+        "ino" => args.bin_op(Ino),
+        "inx" => args.bin_op(Inx),
+        "app" => args.single(App),
+        "par" => args.un_op(Par),
+        "clf" => args.single(Clf),
+        "ret" => args.un_op(Ret),
+        "in" => {
+            args.expect(0, 2)?;
+            Ok(In(args.bin()?))
+        }
+        "out" => {
+            args.expect(0, 1)?;
+            Ok(Out(args.un()))
+        }
+        "fls" => args.none(Fls),
+        "sfd" => args.single(Sfd),
+        "gfd" => args.single(Gfd),
+        "zer" => {
+            args.expect(0, 2)?;
+            Ok(Zer(args.plain(0)?, args.plain(1)?))
+        }
+        "cmp" => args.triple(Cmp),
+        "cpy" => args.triple(Cpy),
+        _ => Err(AssembleError::new(AssembleErrorKind::UnknownMnemonic, pos)),
+    }
+}
+
+pub fn assemble(code: &str) -> Result<Vec<Op>, AssembleError> {
+    let program = AsmParser::parse(Rule::program, code)?.next().unwrap();
+
+    // The first pass collects labels, so they can be referenced before definition
+    let mut labels = Labels::new();
+    let mut idx: UWord = 0;
+
+    for pair in program.clone().into_inner() {
+        match pair.as_rule() {
+            Rule::label => {
+                let pos = pair.as_span().start_pos().line_col();
+                let name = pair.into_inner().next().unwrap().as_str();
+
+                if labels.insert(name, idx).is_some() {
+                    return Err(AssembleError::new(AssembleErrorKind::DuplicateLabel, pos));
+                }
+            }
+            Rule::op => idx += 1,
+            _ => (),
+        }
+    }
+
+    program
+        .into_inner()
+        .filter(|pair| pair.as_rule() == Rule::op)
+        .enumerate()
+        .map(|(idx, pair)| assemble_op(pair, idx as UWord, &labels))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assemble_program() {
+        let code = r#"
+            // hello
+            set u8 loc(0) 72
+            add u32 loc(2){4} val(5)    /* first */
+            div i16 ^8 &16{.5}
+            mod u32 ret(8){5} ref(16){val(5)}
+            inc uw *3
+            out loc(0){loc(6)}
+            cnv i64 u8 loc(0) loc(8)
+            shl u16 loc(0) 0b11
+            jmp -2
+            in loc(0) emp
+            cpy loc(0) loc(1) 0x0C
+            end 0
+        "#;
+
+        let expected = vec![
+            Op::Set(BinOp::new(Operand::Loc(0), Operand::Val(72)), OpType::U8),
+            Op::Add(
+                BinOp::new(Operand::Loc(2), Operand::Val(5)).with_first(Operand::Val(4)),
+                OpType::U32,
+            ),
+            Op::Div(
+                BinOp::new(Operand::Ret(8), Operand::Ref(16)).with_second(Operand::Glb(5)),
+                OpType::I16,
+            ),
+            Op::Mod(
+                BinOp::new(Operand::Ret(8), Operand::Ref(16)).with_both(Operand::Val(5)),
+                OpType::U32,
+            ),
+            Op::Inc(UnOp::new(Operand::Ind(3)), OpType::Uw),
+            Op::Out(UnOp::new(Operand::Loc(0)).with_first(Operand::Loc(6))),
+            Op::Cnv(Operand::Loc(0), Operand::Loc(8), OpType::I64, OpType::U8),
+            Op::Shl(Operand::Loc(0), Operand::Val(3), OpType::U16),
+            Op::Jmp(Operand::Val(-2 as IWord as UWord)),
+            Op::In(BinOp::new(Operand::Loc(0), Operand::Emp)),
+            Op::Cpy(Operand::Loc(0), Operand::Loc(1), Operand::Val(12)),
+            Op::End(Operand::Val(0)),
+        ];
+
+        assert_eq!(assemble(code), Ok(expected));
+    }
+
+    #[test]
+    fn assemble_labels() {
+        let code = r#"
+            start:
+                set uw loc(0) 0
+            loop: inc uw loc(0)
+                ifl uw loc(0) 6
+                jmp loop
+                go end
+                go start
+            end:
+            exit: end loc(0)
+        "#;
+
+        let expected = vec![
+            Op::Set(BinOp::new(Operand::Loc(0), Operand::Val(0)), OpType::Uw),
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::Uw),
+            Op::Ifl(BinOp::new(Operand::Loc(0), Operand::Val(6)), OpType::Uw),
+            Op::Jmp(Operand::Val(-2 as IWord as UWord)),
+            Op::Go(Operand::Val(6)),
+            Op::Go(Operand::Val(0)),
+            Op::End(Operand::Loc(0)),
+        ];
+
+        assert_eq!(assemble(code), Ok(expected));
+    }
+
+    #[test]
+    fn assemble_display() {
+        let ops = [
+            Op::Nop,
+            Op::Set(BinOp::new(Operand::Loc(0), Operand::Val(72)), OpType::U8),
+            Op::Sub(
+                BinOp::new(Operand::Loc(2), Operand::Val(5)).with_first(Operand::Val(4)),
+                OpType::F64,
+            ),
+            Op::Ifg(
+                BinOp::new(Operand::Ret(8), Operand::Ref(16)).with_second(Operand::Glb(5)),
+                OpType::I16,
+            ),
+            Op::Xor(
+                BinOp::new(Operand::Ind(8), Operand::Ref(16)).with_both(Operand::Val(5)),
+                OpType::Iw,
+            ),
+            Op::Par(
+                UnOp::new(Operand::Ind(3)).with_first(Operand::Loc(1)),
+                OpType::Uw,
+            ),
+            Op::Cnv(Operand::Loc(0), Operand::Loc(8), OpType::I64, OpType::U8),
+            Op::Shr(Operand::Loc(0), Operand::Val(3), OpType::U16),
+            Op::In(BinOp::new(Operand::Loc(0), Operand::Emp)),
+            Op::Out(UnOp::new(Operand::Loc(0))),
+            Op::Zer(Operand::Ref(0), Operand::Val(8)),
+            Op::Cmp(Operand::Loc(0), Operand::Loc(1), Operand::Val(12)),
+            Op::End(Operand::Emp),
+        ];
+
+        assert_eq!(ops[2].to_string(), "sub f64 loc(2){val(4)} val(5)");
+
+        let code: Vec<_> = ops.iter().map(Op::to_string).collect();
+        assert_eq!(assemble(&code.join("\n")), Ok(ops.to_vec()));
+    }
+
+    #[test]
+    fn assemble_errors() {
+        let err = |kind, line, column| Err(AssembleError { kind, line, column });
+
+        assert_eq!(
+            assemble("nop\nset u8 loc(0"),
+            err(AssembleErrorKind::Syntax, 2, 11),
+        );
+        assert_eq!(
+            assemble("nop\n  jump 1"),
+            err(AssembleErrorKind::UnknownMnemonic, 2, 3),
+        );
+        assert_eq!(
+            assemble("add loc(0) 1"),
+            err(
+                AssembleErrorKind::WrongTypeCount {
+                    expected: 1,
+                    found: 0
+                },
+                1,
+                1
+            ),
+        );
+        assert_eq!(
+            assemble("end 1 2"),
+            err(
+                AssembleErrorKind::WrongOperandCount {
+                    expected: 1,
+                    found: 2
+                },
+                1,
+                1
+            ),
+        );
+        assert_eq!(
+            assemble("go 1{2}"),
+            err(AssembleErrorKind::UnexpectedOffset, 1, 4),
+        );
+        assert_eq!(
+            assemble("set u8 loc(0){1} loc(1){2}"),
+            err(AssembleErrorKind::DifferentOffsets, 1, 18),
+        );
+        assert_eq!(
+            assemble("end 0x1_0000_0000_0000_0000_0000"),
+            err(AssembleErrorKind::TooLargeValue, 1, 5),
+        );
+        assert_eq!(
+            assemble("go loop\nend 0"),
+            err(AssembleErrorKind::UndefinedLabel, 1, 4),
+        );
+        assert_eq!(
+            assemble("a:\nnop\n  a: end 0"),
+            err(AssembleErrorKind::DuplicateLabel, 3, 3),
+        );
+        assert_eq!(
+            assemble("jmp -0xFFFF_FFFF_FFFF_FFFF"),
+            err(AssembleErrorKind::TooLargeValue, 1, 5),
+        );
+    }
+}
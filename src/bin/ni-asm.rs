@@ -0,0 +1,63 @@
+//! Assembles text programs into a module.
+//!
+//! Each input is a file with one function and its frame size, written as `PATH:FRAME_SIZE`.
+//! The function is named after the file stem. The first input is called by `ni-run`.
+
+use ni::{
+    assembler::assemble,
+    common::UWord,
+    encoder::encode::Encode,
+    module::{Module, ModuleFunction},
+    validator::validate_with_frame,
+};
+use std::{fs, path::Path, process};
+
+const USAGE: &str = "usage: ni-asm OUTPUT PATH:FRAME_SIZE...";
+
+fn assemble_function(input: &str) -> Result<ModuleFunction, String> {
+    let (path, frame_size) = input
+        .rsplit_once(':')
+        .ok_or_else(|| format!("{}: missing frame size", input))?;
+
+    let frame_size: UWord = frame_size
+        .parse()
+        .map_err(|_| format!("{}: incorrect frame size", input))?;
+
+    let code = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let program = assemble(&code).map_err(|e| format!("{}:{}", path, e))?;
+    validate_with_frame(&program, frame_size).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut function = ModuleFunction::new(frame_size, program);
+    if let Some(name) = Path::new(path).file_stem().and_then(|s| s.to_str()) {
+        function = function.with_name(name);
+    }
+
+    Ok(function)
+}
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (output, inputs) = match args.split_first() {
+        Some((output, inputs)) if !inputs.is_empty() => (output, inputs),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut module = Module::new();
+    for input in inputs {
+        module.push(assemble_function(input)?);
+    }
+
+    let mut blob = vec![];
+    module
+        .encode(&mut blob)
+        .map_err(|e| format!("{}: {}", output, e))?;
+
+    fs::write(output, blob).map_err(|e| format!("{}: {}", output, e))
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("ni-asm: {}", e);
+        process::exit(1);
+    }
+}
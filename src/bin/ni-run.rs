@@ -0,0 +1,167 @@
+//! Runs a module with the interpreter.
+//!
+//! The first function of the module is called. Standard input is file 0 and
+//! standard output is file 1, which is set as current. The exit code is the
+//! value passed to `end`.
+
+use ni::{
+    common::Op,
+    decoder::decode::decode_with,
+    executor::{
+        debugger::{Debugger, Location},
+        files::{File, FileError},
+        ExecutionError, ExecutionSuccess, Executor,
+    },
+    module::{Checksum, Module},
+};
+use std::{
+    any::Any,
+    fs,
+    io::{self, Read, Write},
+    process, thread,
+    time::Duration,
+};
+
+const USAGE: &str = "usage: ni-run [--trace] [--fuel N] MODULE";
+
+#[derive(Debug)]
+struct Stdin(io::Stdin);
+
+impl File for Stdin {
+    fn read(&mut self) -> Result<Option<u8>, FileError> {
+        let mut buf = [0];
+        match self.0.read(&mut buf) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(buf[0])),
+            Err(_) => Err(FileError::ReadingNotAvailable),
+        }
+    }
+
+    fn write(&mut self, _: u8) -> Result<(), FileError> {
+        Err(FileError::WritingNotAvailable)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Stdout(io::Stdout);
+
+impl File for Stdout {
+    fn read(&mut self) -> Result<Option<u8>, FileError> {
+        Err(FileError::ReadingNotAvailable)
+    }
+
+    fn write(&mut self, val: u8) -> Result<(), FileError> {
+        self.0
+            .write_all(&[val])
+            .map_err(|_| FileError::WritingNotAvailable)
+    }
+
+    fn flush(&mut self) -> Result<(), FileError> {
+        self.0.flush().map_err(|_| FileError::WritingNotAvailable)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Prints each executed operation to standard error.
+#[derive(Debug)]
+struct Trace;
+
+impl Debugger for Trace {
+    fn on_step(&mut self, location: Location, op: &Op) {
+        eprintln!("{}:{}\t{}", location.function, location.op, op);
+    }
+
+    fn on_trap(&mut self, location: Location, error: ExecutionError) {
+        eprintln!("{}:{}\ttrap: {}", location.function, location.op, error);
+    }
+}
+
+struct Args {
+    trace: bool,
+    fuel: Option<u64>,
+    path: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let mut trace = false;
+    let mut fuel = None;
+    let mut path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => trace = true,
+            "--fuel" => {
+                let n = args.next().ok_or(USAGE)?;
+                fuel = Some(n.parse().map_err(|_| format!("incorrect fuel {}", n))?);
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    Ok(Args {
+        trace,
+        fuel,
+        path: path.ok_or(USAGE)?,
+    })
+}
+
+fn run() -> Result<i32, String> {
+    let args = parse_args()?;
+
+    let blob = fs::read(&args.path).map_err(|e| format!("{}: {}", args.path, e))?;
+    let module: Module = decode_with(&mut blob.as_slice(), Checksum::Verify)
+        .map_err(|e| format!("{}: {}", args.path, e))?;
+
+    let functions = module.executor_functions();
+    let mut exe = Executor::new(&functions);
+    exe.set_fuel(args.fuel);
+
+    if args.trace {
+        exe.attach(Trace);
+    }
+
+    let files = exe.files();
+    files
+        .open(Stdin(io::stdin()))
+        .map_err(|(e, _)| e.to_string())?;
+    let stdout = files
+        .open(Stdout(io::stdout()))
+        .map_err(|(e, _)| e.to_string())?;
+    files.set_current(stdout).map_err(|e| e.to_string())?;
+
+    exe.call(0, 0).map_err(|e| e.to_string())?;
+
+    loop {
+        match exe.run() {
+            Ok(ExecutionSuccess::End(code)) => break Ok(code as i32),
+            Ok(ExecutionSuccess::Sleep(ms)) => thread::sleep(Duration::from_millis(ms as _)),
+            Ok(_) => continue,
+            Err(e) => match exe.location() {
+                Some(at) => break Err(format!("{}:{}: {}", at.function, at.op, e)),
+                None => break Err(e.to_string()),
+            },
+        }
+    }
+}
+
+fn main() {
+    let res = run();
+    let _ = io::stdout().flush();
+
+    match res {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("ni-run: {}", e);
+            process::exit(1);
+        }
+    }
+}
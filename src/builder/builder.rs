@@ -0,0 +1,393 @@
+use std::collections::HashMap;
+
+use crate::{
+    common::*,
+    validator::{validate, ValidationError},
+};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BuildError<'l> {
+    DifferentOffsets { idx: usize },
+    UndefinedLabel(&'l str),
+    DuplicateLabel(&'l str),
+    Invalid(ValidationError),
+}
+
+impl From<ValidationError> for BuildError<'_> {
+    fn from(e: ValidationError) -> Self {
+        BuildError::Invalid(e)
+    }
+}
+
+pub fn loc(n: UWord) -> Operand {
+    Operand::Loc(n)
+}
+
+pub fn ind(n: UWord) -> Operand {
+    Operand::Ind(n)
+}
+
+pub fn ret(n: UWord) -> Operand {
+    Operand::Ret(n)
+}
+
+pub fn val(n: UWord) -> Operand {
+    Operand::Val(n)
+}
+
+pub fn r#ref(n: UWord) -> Operand {
+    Operand::Ref(n)
+}
+
+pub fn glb(n: UWord) -> Operand {
+    Operand::Glb(n)
+}
+
+pub fn emp() -> Operand {
+    Operand::Emp
+}
+
+/// Operand of an unary or binary operation with an optional offset.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Arg {
+    operand: Operand,
+    offset: Option<Operand>,
+}
+
+impl From<Operand> for Arg {
+    fn from(operand: Operand) -> Self {
+        Self {
+            operand,
+            offset: None,
+        }
+    }
+}
+
+/// Expressed as `x{q}` in the assembler.
+pub fn with_offset(x: Operand, offset: Operand) -> Arg {
+    Arg {
+        operand: x,
+        offset: Some(offset),
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Jump<'l> {
+    Go(&'l str),
+    Jmp(&'l str),
+}
+
+macro_rules! bin_ops {
+    ($($name:ident => $op:ident,)*) => {
+        $(
+            pub fn $name<X, Y>(self, ot: OpType, x: X, y: Y) -> Self
+            where
+                X: Into<Arg>,
+                Y: Into<Arg>,
+            {
+                self.bin(x.into(), y.into(), |bin| Op::$op(bin, ot))
+            }
+        )*
+    };
+}
+
+macro_rules! un_ops {
+    ($($name:ident => $op:ident,)*) => {
+        $(
+            pub fn $name<X>(self, ot: OpType, x: X) -> Self
+            where
+                X: Into<Arg>,
+            {
+                self.op(Op::$op(make_un(x.into()), ot))
+            }
+        )*
+    };
+}
+
+macro_rules! single_ops {
+    ($($name:ident => $op:ident,)*) => {
+        $(
+            pub fn $name(self, x: Operand) -> Self {
+                self.op(Op::$op(x))
+            }
+        )*
+    };
+}
+
+fn make_un(x: Arg) -> UnOp {
+    match x.offset {
+        None => UnOp::new(x.operand),
+        Some(offset) => UnOp::new(x.operand).with_first(offset),
+    }
+}
+
+/// Builds a program operation by operation.
+///
+/// The operation variant is selected by the offsets of the operands.
+#[derive(Debug, Default)]
+pub struct ProgramBuilder<'l> {
+    ops: Vec<Op>,
+    labels: HashMap<&'l str, UWord>,
+    jumps: Vec<(usize, Jump<'l>)>,
+    error: Option<BuildError<'l>>,
+}
+
+impl<'l> ProgramBuilder<'l> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn fail(mut self, error: BuildError<'l>) -> Self {
+        self.error.get_or_insert(error);
+        self
+    }
+
+    pub fn op(mut self, op: Op) -> Self {
+        self.ops.push(op);
+        self
+    }
+
+    fn bin<F>(self, x: Arg, y: Arg, f: F) -> Self
+    where
+        F: FnOnce(BinOp) -> Op,
+    {
+        let bin = BinOp::new(x.operand, y.operand);
+        let bin = match (x.offset, y.offset) {
+            (None, None) => bin,
+            (Some(offset), None) => bin.with_first(offset),
+            (None, Some(offset)) => bin.with_second(offset),
+            (Some(a), Some(b)) if a == b => bin.with_both(a),
+            _ => {
+                let idx = self.ops.len();
+                return self.fail(BuildError::DifferentOffsets { idx });
+            }
+        };
+
+        self.op(f(bin))
+    }
+
+    /// Marks the position of the next operation.
+    pub fn label(mut self, name: &'l str) -> Self {
+        let idx = self.ops.len() as UWord;
+        if self.labels.insert(name, idx).is_some() {
+            return self.fail(BuildError::DuplicateLabel(name));
+        }
+
+        self
+    }
+
+    /// Goes to the label.
+    pub fn go_to(mut self, label: &'l str) -> Self {
+        self.jumps.push((self.ops.len(), Jump::Go(label)));
+        self.op(Op::Go(Operand::Val(0)))
+    }
+
+    /// Jumps to the label relative to the current operation.
+    pub fn jmp_to(mut self, label: &'l str) -> Self {
+        self.jumps.push((self.ops.len(), Jump::Jmp(label)));
+        self.op(Op::Jmp(Operand::Val(0)))
+    }
+
+    bin_ops! {
+        set => Set,
+        add => Add,
+        sub => Sub,
+        mul => Mul,
+        div => Div,
+        r#mod => Mod,
+        and => And,
+        or => Or,
+        xor => Xor,
+        min => Min,
+        max => Max,
+        ife => Ife,
+        ifl => Ifl,
+        ifg => Ifg,
+        ine => Ine,
+        inl => Inl,
+        ing => Ing,
+        ifa => Ifa,
+        ifo => Ifo,
+        ifx => Ifx,
+        ina => Ina,
+        ino => Ino,
+        inx => Inx,
+    }
+
+    un_ops! {
+        not => Not,
+        neg => Neg,
+        inc => Inc,
+        dec => Dec,
+        sqt => Sqt,
+        abs => Abs,
+        ift => Ift,
+        iff => Iff,
+        par => Par,
+        ret => Ret,
+    }
+
+    single_ops! {
+        end => End,
+        slp => Slp,
+        go => Go,
+        jmp => Jmp,
+        app => App,
+        clf => Clf,
+        sfd => Sfd,
+        gfd => Gfd,
+    }
+
+    pub fn nop(self) -> Self {
+        self.op(Op::Nop)
+    }
+
+    /// Converts `y` of type `from` and writes it to `x` of type `to`.
+    pub fn cnv(self, from: OpType, to: OpType, x: Operand, y: Operand) -> Self {
+        self.op(Op::Cnv(x, y, from, to))
+    }
+
+    pub fn shl(self, ot: OpType, x: Operand, y: Operand) -> Self {
+        self.op(Op::Shl(x, y, ot))
+    }
+
+    pub fn shr(self, ot: OpType, x: Operand, y: Operand) -> Self {
+        self.op(Op::Shr(x, y, ot))
+    }
+
+    pub fn r#in<X, Y>(self, x: X, y: Y) -> Self
+    where
+        X: Into<Arg>,
+        Y: Into<Arg>,
+    {
+        self.bin(x.into(), y.into(), Op::In)
+    }
+
+    pub fn out<X>(self, x: X) -> Self
+    where
+        X: Into<Arg>,
+    {
+        self.op(Op::Out(make_un(x.into())))
+    }
+
+    pub fn fls(self) -> Self {
+        self.op(Op::Fls)
+    }
+
+    pub fn zer(self, x: Operand, y: Operand) -> Self {
+        self.op(Op::Zer(x, y))
+    }
+
+    pub fn cmp(self, x: Operand, y: Operand, z: Operand) -> Self {
+        self.op(Op::Cmp(x, y, z))
+    }
+
+    pub fn cpy(self, x: Operand, y: Operand, z: Operand) -> Self {
+        self.op(Op::Cpy(x, y, z))
+    }
+
+    /// Resolves the labels and validates the program.
+    pub fn finish(mut self) -> Result<Vec<Op>, BuildError<'l>> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        for (idx, jump) in self.jumps {
+            let label = match jump {
+                Jump::Go(label) | Jump::Jmp(label) => label,
+            };
+
+            let target = *self
+                .labels
+                .get(label)
+                .ok_or(BuildError::UndefinedLabel(label))?;
+
+            self.ops[idx] = match jump {
+                Jump::Go(_) => Op::Go(Operand::Val(target)),
+                Jump::Jmp(_) => Op::Jmp(Operand::Val(target.wrapping_sub(idx as UWord))),
+            };
+        }
+
+        validate(&self.ops)?;
+        Ok(self.ops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    #[test]
+    fn build_program() {
+        let ops = ProgramBuilder::new()
+            .set(OpType::U32, loc(0), val(5))
+            .add(OpType::U32, with_offset(loc(0), loc(4)), ind(8))
+            .sub(OpType::U32, loc(0), with_offset(ind(8), loc(4)))
+            .mul(
+                OpType::U32,
+                with_offset(loc(0), val(1)),
+                with_offset(glb(3), val(1)),
+            )
+            .label("loop")
+            .inc(OpType::U8, loc(12))
+            .ifl(OpType::U8, loc(12), val(10))
+            .jmp_to("loop")
+            .go_to("end")
+            .label("end")
+            .r#in(loc(0), emp())
+            .ret(OpType::U8, emp())
+            .finish()
+            .unwrap();
+
+        let expected = assemble(
+            r#"
+            set u32 loc(0) 5
+            add u32 loc(0){loc(4)} *8
+            sub u32 loc(0) *8{loc(4)}
+            mul u32 loc(0){1} glb(3){1}
+            inc u8 loc(12)
+            ifl u8 loc(12) 10
+            jmp -2
+            go 8
+            in loc(0) emp
+            ret u8 emp
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(ops, expected);
+    }
+
+    #[test]
+    fn build_errors() {
+        use crate::validator::ValidationErrorKind;
+
+        let res = ProgramBuilder::new()
+            .set(
+                OpType::U8,
+                with_offset(loc(0), val(1)),
+                with_offset(loc(1), val(2)),
+            )
+            .finish();
+        assert_eq!(res, Err(BuildError::DifferentOffsets { idx: 0 }));
+
+        let res = ProgramBuilder::new().label("a").label("a").finish();
+        assert_eq!(res, Err(BuildError::DuplicateLabel("a")));
+
+        let res = ProgramBuilder::new().jmp_to("a").finish();
+        assert_eq!(res, Err(BuildError::UndefinedLabel("a")));
+
+        let res = ProgramBuilder::new()
+            .set(OpType::U8, val(0), val(1))
+            .end(val(0))
+            .finish();
+        assert_eq!(
+            res,
+            Err(BuildError::Invalid(ValidationError {
+                kind: ValidationErrorKind::WriteToConstant,
+                idx: 0,
+            }))
+        );
+    }
+}
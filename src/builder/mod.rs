@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+mod builder;
+
+pub use builder::*;
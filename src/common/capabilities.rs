@@ -0,0 +1,81 @@
+/// Set of features a program requires from the runtime.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// Operations on `f32` and `f64`.
+    pub const FLOATS: Self = Self(1 << 0);
+
+    /// Atomic operations. Reserved, no operation requires it yet.
+    pub const ATOMICS: Self = Self(1 << 1);
+
+    /// Vector operations. Reserved, no operation requires it yet.
+    pub const VECTORS: Self = Self(1 << 2);
+
+    /// Capabilities of this runtime.
+    pub const SUPPORTED: Self = Self::FLOATS;
+
+    const NAMES: [(Self, &'static str); 3] = [
+        (Self::FLOATS, "floats"),
+        (Self::ATOMICS, "atomics"),
+        (Self::VECTORS, "vectors"),
+    ];
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Capabilities of `self` which are not in `other`.
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl std::fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut rest = *self;
+        let mut first = true;
+
+        for &(cap, name) in &Self::NAMES {
+            if self.contains(cap) {
+                if !first {
+                    write!(f, ", ")?;
+                }
+
+                write!(f, "{}", name)?;
+                rest = rest.difference(cap);
+                first = false;
+            }
+        }
+
+        if !rest.is_empty() {
+            if !first {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{:#x}", rest.0)?;
+        }
+
+        Ok(())
+    }
+}
@@ -2,10 +2,12 @@
 mod tests;
 
 pub mod bits;
+mod capabilities;
 mod expected;
 pub mod op_codes;
 mod operations;
 
+pub use capabilities::*;
 pub use expected::*;
 pub use operations::*;
 
@@ -18,3 +20,11 @@ pub type IWord = i32;
 pub type UWord = u64;
 #[cfg(feature = "w64")]
 pub type IWord = i64;
+
+/// Byte order of operand values in the encoded code.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
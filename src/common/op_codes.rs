@@ -1,3 +1,23 @@
+/// Version of the operation table.
+///
+/// Version 1 is the table the module format was introduced with, so it contains
+/// every operation defined so far. Operations added later bump the version.
+pub const ISA_VERSION: u16 = 1;
+
+/// Version of the operation table that introduced the operation code, `None` if the code is unknown.
+///
+/// A new operation code has to be added here with the version it's introduced in.
+/// `OPN`, `CLS` and `GIV` are reserved and have no operation yet.
+pub fn isa_version(op_code: u8) -> Option<u16> {
+    match op_code {
+        NOP | END | SLP | SET | CNV | ADD | SUB | MUL | DIV | MOD | SHL | SHR | AND | OR | XOR
+        | NOT | NEG | INC | DEC | GO | IFT | IFF | IFE | IFL | IFG | INE | INL | ING | IFA
+        | IFO | IFX | INA | INO | INX | APP | PAR | CLF | RET | IN | OUT | FLS | SFD | GFD
+        | ZER | CMP | CPY | JMP | SQT | ABS | MIN | MAX => Some(1),
+        _ => None,
+    }
+}
+
 /// No operation.
 pub const NOP: u8 = 0x00;
 
@@ -144,3 +164,18 @@ pub const CPY: u8 = 0x2F;
 
 /// Get interpreter value.
 pub const GIV: u8 = 0x30;
+
+/// Jump relative.
+pub const JMP: u8 = 0x31;
+
+/// Square root. Like all float operations, it rounds to nearest, ties to even.
+pub const SQT: u8 = 0x32;
+
+/// Absolute value.
+pub const ABS: u8 = 0x33;
+
+/// Minimum. NaN if any operand is NaN, `-0` is less than `+0`.
+pub const MIN: u8 = 0x34;
+
+/// Maximum. NaN if any operand is NaN, `-0` is less than `+0`.
+pub const MAX: u8 = 0x35;
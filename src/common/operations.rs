@@ -1,12 +1,27 @@
-use super::{IWord, UWord};
+use super::{bits::*, Capabilities, IWord, UWord};
 
-#[derive(Debug, Eq, PartialEq)]
+/// A raw value that does not name any operation type, operand kind or variant.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum UndefinedOperation {
-    OpType,
-    Kind,
-    Variant,
+    OpType(u8),
+    Kind(u8),
+    Variant(u8),
+}
+
+impl std::fmt::Display for UndefinedOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use UndefinedOperation::*;
+
+        match self {
+            OpType(val) => write!(f, "undefined operation type {:#x}", val),
+            Kind(val) => write!(f, "undefined operand kind {:#x}", val),
+            Variant(val) => write!(f, "undefined variant {:#x}", val),
+        }
+    }
 }
 
+impl std::error::Error for UndefinedOperation {}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum Operand {
     /// Local variable.
@@ -57,7 +72,7 @@ impl Operand {
             4 => Ref(val),
             5 => Glb(val),
             6 => Emp,
-            _ => return Err(UndefinedOperation::Kind),
+            _ => return Err(UndefinedOperation::Kind(kind)),
         })
     }
 
@@ -129,6 +144,22 @@ impl std::fmt::Debug for Operand {
     }
 }
 
+impl std::fmt::Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Operand::*;
+
+        match self {
+            Loc(v) => write!(f, "loc({})", v),
+            Ind(v) => write!(f, "ind({})", v),
+            Ret(v) => write!(f, "ret({})", v),
+            Val(v) => write!(f, "val({})", v),
+            Ref(v) => write!(f, "ref({})", v),
+            Glb(v) => write!(f, "glb({})", v),
+            Emp => write!(f, "emp"),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum UnOp {
     None { x: Operand },
@@ -163,6 +194,15 @@ impl UnOp {
     }
 }
 
+impl std::fmt::Display for UnOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnOp::None { x } => write!(f, "{}", x),
+            UnOp::First { x, offset } => write!(f, "{}{{{}}}", x, offset),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum BinOp {
     None {
@@ -225,6 +265,17 @@ impl BinOp {
     }
 }
 
+impl std::fmt::Display for BinOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinOp::None { x, y } => write!(f, "{} {}", x, y),
+            BinOp::First { x, y, offset } => write!(f, "{}{{{}}} {}", x, offset, y),
+            BinOp::Second { x, y, offset } => write!(f, "{} {}{{{}}}", x, y, offset),
+            BinOp::Both { x, y, offset } => write!(f, "{}{{{}}} {}{{{}}}", x, offset, y, offset),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum Op {
     Nop,
@@ -246,7 +297,12 @@ pub enum Op {
     Neg(UnOp, OpType),
     Inc(UnOp, OpType),
     Dec(UnOp, OpType),
+    Sqt(UnOp, OpType),
+    Abs(UnOp, OpType),
+    Min(BinOp, OpType),
+    Max(BinOp, OpType),
     Go(Operand),
+    Jmp(Operand),
     Ift(UnOp, OpType),
     Iff(UnOp, OpType),
     Ife(BinOp, OpType),
@@ -279,10 +335,136 @@ impl Op {
     pub fn is_conditional(&self) -> bool {
         use Op::*;
 
-        match self {
-            Ift(..) | Iff(..) | Ife(..) | Ifl(..) | Ifg(..) | Ine(..) | Inl(..) | Ing(..)
-            | Ifa(..) | Ifo(..) | Ifx(..) | Ina(..) | Ino(..) | Inx(..) | Cmp(..) => true,
+        matches!(
+            self,
+            Ift(..)
+                | Iff(..)
+                | Ife(..)
+                | Ifl(..)
+                | Ifg(..)
+                | Ine(..)
+                | Inl(..)
+                | Ing(..)
+                | Ifa(..)
+                | Ifo(..)
+                | Ifx(..)
+                | Ina(..)
+                | Ino(..)
+                | Inx(..)
+                | Cmp(..)
+        )
+    }
+
+    /// Version of the operation table that introduced the operation.
+    pub fn isa_version(&self) -> u16 {
+        super::op_codes::isa_version(self.op_code()).expect("the operation code has a version")
+    }
+
+    /// Capabilities a runtime needs to execute the operation.
+    pub fn capabilities(&self) -> Capabilities {
+        use Op::*;
+
+        let float = match *self {
+            Cnv(_, _, t, u) => t.is_float() || u.is_float(),
+            Shl(_, _, t) | Shr(_, _, t) => t.is_float(),
+            Set(_, t)
+            | Add(_, t)
+            | Sub(_, t)
+            | Mul(_, t)
+            | Div(_, t)
+            | Mod(_, t)
+            | And(_, t)
+            | Or(_, t)
+            | Xor(_, t)
+            | Min(_, t)
+            | Max(_, t)
+            | Ife(_, t)
+            | Ifl(_, t)
+            | Ifg(_, t)
+            | Ine(_, t)
+            | Inl(_, t)
+            | Ing(_, t)
+            | Ifa(_, t)
+            | Ifo(_, t)
+            | Ifx(_, t)
+            | Ina(_, t)
+            | Ino(_, t)
+            | Inx(_, t) => t.is_float(),
+            Not(_, t)
+            | Neg(_, t)
+            | Inc(_, t)
+            | Dec(_, t)
+            | Sqt(_, t)
+            | Abs(_, t)
+            | Ift(_, t)
+            | Iff(_, t)
+            | Par(_, t)
+            | Ret(_, t) => t.is_float(),
             _ => false,
+        };
+
+        if float {
+            Capabilities::FLOATS
+        } else {
+            Capabilities::empty()
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        use Op::*;
+
+        match self {
+            Nop => "nop",
+            End(..) => "end",
+            Slp(..) => "slp",
+            Set(..) => "set",
+            Cnv(..) => "cnv",
+            Add(..) => "add",
+            Sub(..) => "sub",
+            Mul(..) => "mul",
+            Div(..) => "div",
+            Mod(..) => "mod",
+            Shl(..) => "shl",
+            Shr(..) => "shr",
+            And(..) => "and",
+            Or(..) => "or",
+            Xor(..) => "xor",
+            Not(..) => "not",
+            Neg(..) => "neg",
+            Inc(..) => "inc",
+            Dec(..) => "dec",
+            Sqt(..) => "sqt",
+            Abs(..) => "abs",
+            Min(..) => "min",
+            Max(..) => "max",
+            Go(..) => "go",
+            Jmp(..) => "jmp",
+            Ift(..) => "ift",
+            Iff(..) => "iff",
+            Ife(..) => "ife",
+            Ifl(..) => "ifl",
+            Ifg(..) => "ifg",
+            Ine(..) => "ine",
+            Inl(..) => "inl",
+            Ing(..) => "ing",
+            Ifa(..) => "ifa",
+            Ifo(..) => "ifo",
+            Ifx(..) => "ifx",
+            Ina(..) => "ina",
+            Ino(..) => "ino",
+            Inx(..) => "inx",
+            App(..) => "app",
+            Par(..) => "par",
+            Clf(..) => "clf",
+            Ret(..) => "ret",
+            In(..) => "in",
+            Out(..) => "out",
+            Fls => "fls",
+            Sfd(..) => "sfd",
+            Gfd(..) => "gfd",
+            Zer(..) => "zer",
+            Cmp(..) => "cmp",
+            Cpy(..) => "cpy",
         }
     }
 
@@ -310,7 +492,12 @@ impl Op {
             Neg(..) => NEG,
             Inc(..) => INC,
             Dec(..) => DEC,
+            Sqt(..) => SQT,
+            Abs(..) => ABS,
+            Min(..) => MIN,
+            Max(..) => MAX,
             Go(..) => GO,
+            Jmp(..) => JMP,
             Ift(..) => IFT,
             Iff(..) => IFF,
             Ife(..) => IFE,
@@ -365,7 +552,12 @@ impl std::fmt::Debug for Op {
             Neg(u, t) => write!(f, "neg {:?} {:?}", t, u),
             Inc(u, t) => write!(f, "inc {:?} {:?}", t, u),
             Dec(u, t) => write!(f, "dec {:?} {:?}", t, u),
+            Sqt(u, t) => write!(f, "sqt {:?} {:?}", t, u),
+            Abs(u, t) => write!(f, "abs {:?} {:?}", t, u),
+            Min(b, t) => write!(f, "min {:?} {:?}", t, b),
+            Max(b, t) => write!(f, "max {:?} {:?}", t, b),
             Go(x) => write!(f, "go  {:?}", x),
+            Jmp(x) => write!(f, "jmp {:?}", x),
             Ift(u, t) => write!(f, "ift {:?} {:?}", t, u),
             Iff(u, t) => write!(f, "iff {:?} {:?}", t, u),
             Ife(b, t) => write!(f, "ife {:?} {:?}", t, b),
@@ -396,6 +588,60 @@ impl std::fmt::Debug for Op {
     }
 }
 
+impl std::fmt::Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Op::*;
+
+        f.write_str(self.mnemonic())?;
+
+        match self {
+            Nop | Fls => Ok(()),
+            End(x) | Slp(x) | Go(x) | Jmp(x) | App(x) | Clf(x) | Sfd(x) | Gfd(x) => {
+                write!(f, " {}", x)
+            }
+            Set(b, t)
+            | Add(b, t)
+            | Sub(b, t)
+            | Mul(b, t)
+            | Div(b, t)
+            | Mod(b, t)
+            | And(b, t)
+            | Or(b, t)
+            | Xor(b, t)
+            | Ife(b, t)
+            | Ifl(b, t)
+            | Ifg(b, t)
+            | Ine(b, t)
+            | Inl(b, t)
+            | Ing(b, t)
+            | Ifa(b, t)
+            | Ifo(b, t)
+            | Ifx(b, t)
+            | Ina(b, t)
+            | Ino(b, t)
+            | Inx(b, t)
+            | Min(b, t)
+            | Max(b, t) => write!(f, " {} {}", t, b),
+            Not(u, t)
+            | Neg(u, t)
+            | Inc(u, t)
+            | Dec(u, t)
+            | Sqt(u, t)
+            | Abs(u, t)
+            | Ift(u, t)
+            | Iff(u, t)
+            | Par(u, t)
+            | Ret(u, t) => write!(f, " {} {}", t, u),
+            Cnv(x, y, t, u) => write!(f, " {} {} {} {}", t, u, x, y),
+            Shl(x, y, t) | Shr(x, y, t) => write!(f, " {} {} {}", t, x, y),
+            In(b) => write!(f, " {}", b),
+            Out(u) => write!(f, " {}", u),
+            Zer(x, y) => write!(f, " {} {}", x, y),
+            Cmp(x, y, z) | Cpy(x, y, z) => write!(f, " {} {} {}", x, y, z),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum OpType {
     U8,
@@ -429,7 +675,7 @@ impl OpType {
             9 => Iw,
             11 => F32,
             13 => F64,
-            _ => return Err(UndefinedOperation::OpType),
+            _ => return Err(UndefinedOperation::OpType(value)),
         })
     }
 
@@ -470,6 +716,80 @@ impl OpType {
             F64 => std::mem::size_of::<f64>() as UWord,
         }
     }
+
+    pub fn align(&self) -> UWord {
+        use OpType::*;
+
+        match self {
+            U8 => std::mem::align_of::<u8>() as UWord,
+            I8 => std::mem::align_of::<i8>() as UWord,
+            U16 => std::mem::align_of::<u16>() as UWord,
+            I16 => std::mem::align_of::<i16>() as UWord,
+            U32 => std::mem::align_of::<u32>() as UWord,
+            I32 => std::mem::align_of::<i32>() as UWord,
+            U64 => std::mem::align_of::<u64>() as UWord,
+            I64 => std::mem::align_of::<i64>() as UWord,
+            Uw => std::mem::align_of::<UWord>() as UWord,
+            Iw => std::mem::align_of::<IWord>() as UWord,
+            F32 => std::mem::align_of::<f32>() as UWord,
+            F64 => std::mem::align_of::<f64>() as UWord,
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        use OpType::*;
+
+        matches!(self, I8 | I16 | I32 | I64 | Iw | F32 | F64)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, OpType::F32 | OpType::F64)
+    }
+
+    pub fn min(&self) -> Limit {
+        use OpType::*;
+
+        match self {
+            U8 => Limit::Int(u8::MIN as i128),
+            I8 => Limit::Int(i8::MIN as i128),
+            U16 => Limit::Int(u16::MIN as i128),
+            I16 => Limit::Int(i16::MIN as i128),
+            U32 => Limit::Int(u32::MIN as i128),
+            I32 => Limit::Int(i32::MIN as i128),
+            U64 => Limit::Int(u64::MIN as i128),
+            I64 => Limit::Int(i64::MIN as i128),
+            Uw => Limit::Int(UWord::MIN as i128),
+            Iw => Limit::Int(IWord::MIN as i128),
+            F32 => Limit::Float(f32::MIN as f64),
+            F64 => Limit::Float(f64::MIN),
+        }
+    }
+
+    pub fn max(&self) -> Limit {
+        use OpType::*;
+
+        match self {
+            U8 => Limit::Int(u8::MAX as i128),
+            I8 => Limit::Int(i8::MAX as i128),
+            U16 => Limit::Int(u16::MAX as i128),
+            I16 => Limit::Int(i16::MAX as i128),
+            U32 => Limit::Int(u32::MAX as i128),
+            I32 => Limit::Int(i32::MAX as i128),
+            U64 => Limit::Int(u64::MAX as i128),
+            I64 => Limit::Int(i64::MAX as i128),
+            Uw => Limit::Int(UWord::MAX as i128),
+            Iw => Limit::Int(IWord::MAX as i128),
+            F32 => Limit::Float(f32::MAX as f64),
+            F64 => Limit::Float(f64::MAX),
+        }
+    }
+}
+
+/// The smallest or largest value of an `OpType`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Limit {
+    Int(i128),
+    Float(f64),
 }
 
 impl std::fmt::Debug for OpType {
@@ -493,9 +813,33 @@ impl std::fmt::Debug for OpType {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+impl std::fmt::Display for OpType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use OpType::*;
+
+        let s = match self {
+            U8 => "u8",
+            I8 => "i8",
+            U16 => "u16",
+            I16 => "i16",
+            U32 => "u32",
+            I32 => "i32",
+            U64 => "u64",
+            I64 => "i64",
+            Uw => "uw",
+            Iw => "iw",
+            F32 => "f32",
+            F64 => "f64",
+        };
+
+        f.write_str(s)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub enum Variant {
     /// `x y` variant.
+    #[default]
     None,
 
     /// `x{q} y` variant.
@@ -517,7 +861,7 @@ impl Variant {
             1 => First,
             2 => Second,
             3 => Both,
-            _ => return Err(UndefinedOperation::Variant),
+            _ => return Err(UndefinedOperation::Variant(variant)),
         })
     }
 
@@ -533,8 +877,29 @@ impl Variant {
     }
 }
 
-impl Default for Variant {
-    fn default() -> Self {
-        Variant::None
+/// Operation type and variant packed into the spec byte.
+///
+/// The layout is `vv mm tttt`: variant, reserved mode bits and operation type.
+/// The mode bits are ignored for now.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Spec {
+    pub op_type: OpType,
+    pub variant: Variant,
+}
+
+impl Spec {
+    pub fn new(op_type: OpType, variant: Variant) -> Self {
+        Self { op_type, variant }
+    }
+
+    pub fn from_byte(byte: u8) -> Result<Self, UndefinedOperation> {
+        Ok(Self {
+            op_type: OpType::new(byte & OP_TYPE_BITS)?,
+            variant: Variant::new((byte & VARIANT_BITS) >> 6)?,
+        })
+    }
+
+    pub fn to_byte(self) -> u8 {
+        self.variant.as_byte() << 6 | self.op_type.as_byte()
     }
 }
@@ -11,3 +11,33 @@ fn op_size_of() {
 fn op_size_of() {
     assert_eq!(std::mem::size_of::<Op>(), 64)
 }
+
+#[test]
+fn spec_byte() {
+    let spec = Spec::new(OpType::I16, Variant::Second);
+    assert_eq!(spec.to_byte(), 0b1000_0011);
+    assert_eq!(Spec::from_byte(0b1000_0011), Ok(spec));
+
+    assert_eq!(Spec::from_byte(0b1001_0011), Ok(spec));
+    assert_eq!(
+        Spec::from_byte(0b0000_1111),
+        Err(UndefinedOperation::OpType(0b1111))
+    );
+    assert_eq!(
+        UndefinedOperation::OpType(0b1111).to_string(),
+        "undefined operation type 0xf"
+    );
+}
+
+#[test]
+fn op_type_info() {
+    assert_eq!(OpType::U16.size(), 2);
+    assert_eq!(OpType::F64.align(), std::mem::align_of::<f64>() as UWord);
+    assert!(OpType::I8.is_signed());
+    assert!(!OpType::Uw.is_signed());
+    assert!(OpType::F32.is_float());
+    assert!(!OpType::Iw.is_float());
+    assert_eq!(OpType::I8.min(), Limit::Int(-128));
+    assert_eq!(OpType::U64.max(), Limit::Int(u64::MAX as i128));
+    assert_eq!(OpType::F32.max(), Limit::Float(f32::MAX as f64));
+}
@@ -6,9 +6,54 @@ use std::io::{self, Read};
 pub enum DecodeError {
     ReadError(io::Error),
     UnexpectedEnd,
-    UnknownOpCode,
+    UnknownOpCode(u8),
     UndefinedOperation(UndefinedOperation),
     IncorrectVariant,
+    OperandTooWide(usize),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use DecodeError::*;
+
+        match self {
+            ReadError(e) => write!(f, "read error: {}", e),
+            UnexpectedEnd => write!(f, "unexpected end of code"),
+            UnknownOpCode(code) => write!(f, "unknown operation code {:#x}", code),
+            UndefinedOperation(e) => e.fmt(f),
+            IncorrectVariant => write!(f, "incorrect variant"),
+            OperandTooWide(n) => write!(f, "operand of {} bytes does not fit in a word", n),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::ReadError(e) => Some(e),
+            DecodeError::UndefinedOperation(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A decode error together with the offset of the operation that caused it.
+#[derive(Debug)]
+pub struct DisassembleError {
+    pub offset: usize,
+    pub error: DecodeError,
+}
+
+impl std::fmt::Display for DisassembleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at offset {}: {}", self.offset, self.error)
+    }
+}
+
+impl std::error::Error for DisassembleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
 }
 
 impl From<UndefinedOperation> for DecodeError {
@@ -42,206 +87,247 @@ where
     }
 }
 
-fn decode_op<R>(bytes: &mut R) -> Result<Op, DecodeError>
+fn decode_op<R>(bytes: &mut R, e: Endian) -> Result<Op, DecodeError>
 where
     R: Read,
 {
     use op_codes::*;
     use Op::*;
 
-    let op = match bytes.read_u8()? {
+    let op_code = bytes.read_u8()?;
+    let op = match op_code {
         NOP => Nop,
-        END => End(decode(bytes)?),
-        SLP => Slp(decode(bytes)?),
+        END => End(decode_with(bytes, e)?),
+        SLP => Slp(decode_with(bytes, e)?),
         SET => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Set(bin_op, op_type)
         }
         CNV => {
             let (t, u) = decode(bytes)?;
-            Cnv(decode(bytes)?, decode(bytes)?, t, u)
+            Cnv(decode_with(bytes, e)?, decode_with(bytes, e)?, t, u)
         }
         ADD => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Add(bin_op, op_type)
         }
         SUB => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Sub(bin_op, op_type)
         }
         MUL => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Mul(bin_op, op_type)
         }
         DIV => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Div(bin_op, op_type)
         }
         MOD => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Mod(bin_op, op_type)
         }
         SHL => {
             let op_type = decode(bytes)?;
-            let x = decode(bytes)?;
-            let y = decode(bytes)?;
+            let x = decode_with(bytes, e)?;
+            let y = decode_with(bytes, e)?;
             Shl(x, y, op_type)
         }
         SHR => {
             let op_type = decode(bytes)?;
-            let x = decode(bytes)?;
-            let y = decode(bytes)?;
+            let x = decode_with(bytes, e)?;
+            let y = decode_with(bytes, e)?;
             Shr(x, y, op_type)
         }
         AND => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             And(bin_op, op_type)
         }
         OR => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Or(bin_op, op_type)
         }
         XOR => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Xor(bin_op, op_type)
         }
         NOT => {
-            let (op_type, var): (OpType, Variant) = decode(bytes)?;
-            let un_op = decode_with(bytes, var)?;
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
 
             Not(un_op, op_type)
         }
         NEG => {
-            let (op_type, var): (OpType, Variant) = decode(bytes)?;
-            let un_op = decode_with(bytes, var)?;
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
 
             Neg(un_op, op_type)
         }
         INC => {
-            let (op_type, var): (OpType, Variant) = decode(bytes)?;
-            let un_op = decode_with(bytes, var)?;
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
 
             Inc(un_op, op_type)
         }
         DEC => {
-            let (op_type, var): (OpType, Variant) = decode(bytes)?;
-            let un_op = decode_with(bytes, var)?;
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
 
             Dec(un_op, op_type)
         }
-        GO => Go(decode(bytes)?),
+        SQT => {
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
+
+            Sqt(un_op, op_type)
+        }
+        ABS => {
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
+
+            Abs(un_op, op_type)
+        }
+        MIN => {
+            let (bin_op, op_type) = decode_with(bytes, e)?;
+            Min(bin_op, op_type)
+        }
+        MAX => {
+            let (bin_op, op_type) = decode_with(bytes, e)?;
+            Max(bin_op, op_type)
+        }
+        GO => Go(decode_with(bytes, e)?),
+        JMP => Jmp(decode_with(bytes, e)?),
         IFT => {
-            let (op_type, var): (OpType, Variant) = decode(bytes)?;
-            let un_op = decode_with(bytes, var)?;
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
 
             Ift(un_op, op_type)
         }
         IFF => {
-            let (op_type, var): (OpType, Variant) = decode(bytes)?;
-            let un_op = decode_with(bytes, var)?;
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
 
             Iff(un_op, op_type)
         }
         IFE => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ife(bin_op, op_type)
         }
         IFL => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ifl(bin_op, op_type)
         }
         IFG => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ifg(bin_op, op_type)
         }
         INE => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ine(bin_op, op_type)
         }
         INL => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Inl(bin_op, op_type)
         }
         ING => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ing(bin_op, op_type)
         }
         IFA => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ifa(bin_op, op_type)
         }
         IFO => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ifo(bin_op, op_type)
         }
         IFX => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ifx(bin_op, op_type)
         }
         INA => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ina(bin_op, op_type)
         }
         INO => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Ino(bin_op, op_type)
         }
         INX => {
-            let (bin_op, op_type) = decode(bytes)?;
+            let (bin_op, op_type) = decode_with(bytes, e)?;
             Inx(bin_op, op_type)
         }
-        APP => App(decode(bytes)?),
+        APP => App(decode_with(bytes, e)?),
         PAR => {
-            let (op_type, var): (OpType, Variant) = decode(bytes)?;
-            let un_op = decode_with(bytes, var)?;
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
 
             Par(un_op, op_type)
         }
-        CLF => Clf(decode(bytes)?),
+        CLF => Clf(decode_with(bytes, e)?),
         RET => {
-            let (op_type, var): (OpType, Variant) = decode(bytes)?;
-            let un_op = decode_with(bytes, var)?;
+            let Spec { op_type, variant } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
 
             Ret(un_op, op_type)
         }
         IN => {
-            let (_, var): (OpType, Variant) = decode(bytes)?;
-            let bin_op = decode_with(bytes, var)?;
+            let Spec { variant, .. } = decode(bytes)?;
+            let bin_op = decode_with(bytes, (variant, e))?;
 
             In(bin_op)
         }
         OUT => {
-            let (_, var): (OpType, Variant) = decode(bytes)?;
-            let un_op = decode_with(bytes, var)?;
+            let Spec { variant, .. } = decode(bytes)?;
+            let un_op = decode_with(bytes, (variant, e))?;
 
             Out(un_op)
         }
         FLS => Fls,
-        SFD => Sfd(decode(bytes)?),
-        GFD => Gfd(decode(bytes)?),
+        SFD => Sfd(decode_with(bytes, e)?),
+        GFD => Gfd(decode_with(bytes, e)?),
         ZER => {
-            let x = decode(bytes)?;
-            let y = decode(bytes)?;
+            let x = decode_with(bytes, e)?;
+            let y = decode_with(bytes, e)?;
             Zer(x, y)
         }
         CMP => {
-            let x = decode(bytes)?;
-            let y = decode(bytes)?;
-            let z = decode(bytes)?;
+            let x = decode_with(bytes, e)?;
+            let y = decode_with(bytes, e)?;
+            let z = decode_with(bytes, e)?;
             Cmp(x, y, z)
         }
         CPY => {
-            let x = decode(bytes)?;
-            let y = decode(bytes)?;
-            let z = decode(bytes)?;
+            let x = decode_with(bytes, e)?;
+            let y = decode_with(bytes, e)?;
+            let z = decode_with(bytes, e)?;
             Cpy(x, y, z)
         }
-        _ => return Err(DecodeError::UnknownOpCode),
+        _ => return Err(DecodeError::UnknownOpCode(op_code)),
     };
 
     Ok(op)
 }
 
+pub fn disassemble(code: &[u8]) -> impl Iterator<Item = Result<String, DisassembleError>> + '_ {
+    let len = code.len();
+    let mut code = code;
+    let mut failed = false;
+
+    std::iter::from_fn(move || {
+        if failed || code.is_empty() {
+            return None;
+        }
+
+        let offset = len - code.len();
+        let res = decode_op(&mut code, Endian::Little)
+            .map(|op| op.to_string())
+            .map_err(|error| DisassembleError { offset, error });
+        failed = res.is_err();
+        Some(res)
+    })
+}
+
 impl Decode<()> for Op {
     type Err = DecodeError;
 
@@ -249,7 +335,18 @@ impl Decode<()> for Op {
     where
         R: Read,
     {
-        decode_op(bytes)
+        decode_op(bytes, Endian::Little)
+    }
+}
+
+impl Decode<Endian> for Op {
+    type Err = DecodeError;
+
+    fn decode<R>(bytes: &mut R, e: Endian) -> Result<Self, Self::Err>
+    where
+        R: Read,
+    {
+        decode_op(bytes, e)
     }
 }
 
@@ -260,8 +357,19 @@ impl Decode<()> for (BinOp, OpType) {
     where
         R: Read,
     {
-        let (op_type, variant) = decode(bytes)?;
-        let bin_op = decode_with(bytes, variant)?;
+        decode_with(bytes, Endian::Little)
+    }
+}
+
+impl Decode<Endian> for (BinOp, OpType) {
+    type Err = DecodeError;
+
+    fn decode<R>(bytes: &mut R, e: Endian) -> Result<Self, Self::Err>
+    where
+        R: Read,
+    {
+        let Spec { op_type, variant } = decode(bytes)?;
+        let bin_op = decode_with(bytes, (variant, e))?;
 
         Ok((bin_op, op_type))
     }
@@ -274,13 +382,24 @@ impl Decode<Variant> for BinOp {
     where
         R: Read,
     {
-        let bin_op = BinOp::new(decode(bytes)?, decode(bytes)?);
+        decode_with(bytes, (var, Endian::Little))
+    }
+}
+
+impl Decode<(Variant, Endian)> for BinOp {
+    type Err = DecodeError;
+
+    fn decode<R>(bytes: &mut R, (var, e): (Variant, Endian)) -> Result<Self, Self::Err>
+    where
+        R: Read,
+    {
+        let bin_op = BinOp::new(decode_with(bytes, e)?, decode_with(bytes, e)?);
 
         Ok(match var {
             Variant::None => bin_op,
-            Variant::First => bin_op.with_first(decode(bytes)?),
-            Variant::Second => bin_op.with_second(decode(bytes)?),
-            Variant::Both => bin_op.with_both(decode(bytes)?),
+            Variant::First => bin_op.with_first(decode_with(bytes, e)?),
+            Variant::Second => bin_op.with_second(decode_with(bytes, e)?),
+            Variant::Both => bin_op.with_both(decode_with(bytes, e)?),
         })
     }
 }
@@ -292,29 +411,35 @@ impl Decode<Variant> for UnOp {
     where
         R: Read,
     {
-        let un_op = UnOp::new(decode(bytes)?);
+        decode_with(bytes, (var, Endian::Little))
+    }
+}
+
+impl Decode<(Variant, Endian)> for UnOp {
+    type Err = DecodeError;
+
+    fn decode<R>(bytes: &mut R, (var, e): (Variant, Endian)) -> Result<Self, Self::Err>
+    where
+        R: Read,
+    {
+        let un_op = UnOp::new(decode_with(bytes, e)?);
 
         Ok(match var {
             Variant::None => un_op,
-            Variant::First => un_op.with_first(decode(bytes)?),
+            Variant::First => un_op.with_first(decode_with(bytes, e)?),
             _ => return Err(DecodeError::IncorrectVariant),
         })
     }
 }
 
-impl Decode<()> for (OpType, Variant) {
+impl Decode<()> for Spec {
     type Err = DecodeError;
 
     fn decode<R>(bytes: &mut R, _: ()) -> Result<Self, Self::Err>
     where
         R: Read,
     {
-        let meta = bytes.read_u8()?;
-
-        let op_type = OpType::new(meta & OP_TYPE_BITS)?;
-        let variant = Variant::new((meta & VARIANT_BITS) >> 6)?;
-
-        Ok((op_type, variant))
+        Ok(Spec::from_byte(bytes.read_u8()?)?)
     }
 }
 
@@ -355,8 +480,8 @@ impl Decode<()> for UnOp {
     where
         R: Read,
     {
-        let (_, var): (_, Variant) = decode(bytes)?;
-        decode_with(bytes, var)
+        let Spec { variant, .. } = decode(bytes)?;
+        decode_with(bytes, variant)
     }
 }
 
@@ -364,6 +489,17 @@ impl Decode<()> for Operand {
     type Err = DecodeError;
 
     fn decode<R>(bytes: &mut R, _: ()) -> Result<Self, Self::Err>
+    where
+        R: Read,
+    {
+        decode_with(bytes, Endian::Little)
+    }
+}
+
+impl Decode<Endian> for Operand {
+    type Err = DecodeError;
+
+    fn decode<R>(bytes: &mut R, e: Endian) -> Result<Self, Self::Err>
     where
         R: Read,
     {
@@ -373,15 +509,28 @@ impl Decode<()> for Operand {
             return Ok((meta & !LONG_OPERAND_BIT).into());
         }
 
+        let kind = (meta & KIND_BITS) >> 4;
+
+        if kind == Operand::Emp.as_byte() {
+            return Ok(Operand::Emp);
+        }
+
         let n_bytes = (meta & SIZE_BITS) as usize + 1;
         let mut buf = [0; std::mem::size_of::<UWord>()];
 
+        if n_bytes > buf.len() {
+            return Err(DecodeError::OperandTooWide(n_bytes));
+        }
+
         bytes
             .read(&mut buf[..n_bytes])
             .expected::<DecodeError>(n_bytes)?;
 
+        if e == Endian::Big {
+            buf[..n_bytes].reverse();
+        }
+
         let value = UWord::from_le_bytes(buf);
-        let kind = (meta & KIND_BITS) >> 4;
 
         Ok(Operand::new(value, kind)?)
     }
@@ -392,6 +541,41 @@ mod tests {
     use super::*;
     use op_codes::*;
 
+    #[test]
+    fn disassemble_code() {
+        let code = [
+            // set u16 loc(12) ref(8)
+            SET,
+            0b0000_0010,
+            12,
+            0b1100_0000,
+            8,
+            // out loc(0){loc(1)}
+            OUT,
+            0b0100_0000,
+            0,
+            1,
+            // end emp
+            END,
+            0b1110_0000,
+            // inc
+            INC,
+        ];
+
+        let mut lines = disassemble(&code);
+        assert_eq!(lines.next().unwrap().unwrap(), "set u16 loc(12) ref(8)");
+        assert_eq!(lines.next().unwrap().unwrap(), "out loc(0){loc(1)}");
+        assert_eq!(lines.next().unwrap().unwrap(), "end emp");
+        assert!(matches!(
+            lines.next(),
+            Some(Err(DisassembleError {
+                offset: 11,
+                error: DecodeError::UnexpectedEnd,
+            }))
+        ));
+        assert!(lines.next().is_none());
+    }
+
     #[test]
     fn decode_unexpected_end() {
         let code = [
@@ -400,7 +584,7 @@ mod tests {
         ];
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code);
+        let actual = decode_op(&mut code, Endian::Little);
 
         assert!(matches!(actual, Err(DecodeError::UnexpectedEnd)));
         assert!(code.is_empty());
@@ -418,9 +602,62 @@ mod tests {
         ];
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code);
+        let actual = decode_op(&mut code, Endian::Little);
+
+        assert!(matches!(actual, Err(DecodeError::UnknownOpCode(0xFF))));
+    }
+
+    #[test]
+    fn decode_op_code_versions() {
+        for op_code in 0..=u8::MAX {
+            let code = [op_code, 0, 0, 0, 0, 0, 0, 0];
+            let actual = decode_op(&mut code.as_ref(), Endian::Little);
+            let known = !matches!(actual, Err(DecodeError::UnknownOpCode(_)));
+
+            let version = op_codes::isa_version(op_code);
+            assert_eq!(version.is_some(), known, "operation code {:#x}", op_code);
+            if let Some(version) = version {
+                assert!(version <= op_codes::ISA_VERSION);
+            }
+        }
+    }
 
-        assert!(matches!(actual, Err(DecodeError::UnknownOpCode)));
+    #[test]
+    fn decode_undefined_op_type() {
+        let code = [
+            // nop
+            NOP,
+            // inc ? loc(12)
+            INC,
+            0b0000_1111,
+            12,
+        ];
+
+        let mut lines = disassemble(&code);
+        assert_eq!(lines.next().unwrap().unwrap(), "nop");
+
+        let err = lines.next().unwrap().unwrap_err();
+        assert_eq!(err.offset, 1);
+        assert!(matches!(
+            err.error,
+            DecodeError::UndefinedOperation(UndefinedOperation::OpType(0b1111))
+        ));
+        assert_eq!(err.to_string(), "at offset 1: undefined operation type 0xf");
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn decode_operand_too_wide() {
+        let n_bytes = std::mem::size_of::<UWord>() + 1;
+
+        // val(?) with one byte more than a word
+        let mut code = vec![0b1011_0000 | (n_bytes - 1) as u8];
+        code.resize(n_bytes + 1, 0);
+
+        let mut code = code.as_slice();
+        let actual: Result<Operand, _> = decode(&mut code);
+
+        assert!(matches!(actual, Err(DecodeError::OperandTooWide(n)) if n == n_bytes));
     }
 
     #[test]
@@ -436,7 +673,7 @@ mod tests {
         ];
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code);
+        let actual = decode_op(&mut code, Endian::Little);
 
         assert!(matches!(actual, Err(DecodeError::IncorrectVariant)));
     }
@@ -453,7 +690,7 @@ mod tests {
         let expected = Op::Inc(UnOp::new(Operand::Loc(16)), OpType::I16);
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -472,7 +709,7 @@ mod tests {
         let expected = Op::Inc(UnOp::new(Operand::Ind(16)), OpType::I16);
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -496,7 +733,7 @@ mod tests {
         );
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -515,7 +752,7 @@ mod tests {
         let expected = Op::Set(BinOp::new(Operand::Loc(8), Operand::Loc(16)), OpType::I16);
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -537,7 +774,7 @@ mod tests {
         let expected = Op::Add(BinOp::new(Operand::Loc(8), Operand::Ind(16)), OpType::U32);
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -563,7 +800,7 @@ mod tests {
         );
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -589,7 +826,7 @@ mod tests {
         );
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -615,7 +852,7 @@ mod tests {
         );
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -634,7 +871,7 @@ mod tests {
         let expected = Op::Cnv(Operand::Loc(12), Operand::Loc(9), OpType::U8, OpType::U16);
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -653,7 +890,7 @@ mod tests {
         let expected = Op::Shl(Operand::Loc(12), Operand::Loc(9), OpType::U32);
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -681,7 +918,7 @@ mod tests {
         );
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -701,7 +938,7 @@ mod tests {
         let expected = Op::Ifa(BinOp::new(Operand::Loc(12), Operand::Ref(8)), OpType::U32);
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -719,7 +956,7 @@ mod tests {
         let expected = Op::App(Operand::Ref(8));
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -743,7 +980,7 @@ mod tests {
         );
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -761,7 +998,7 @@ mod tests {
         let expected = Op::Ret(UnOp::new(Operand::Loc(16)), OpType::U8);
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -782,7 +1019,7 @@ mod tests {
             Op::In(BinOp::new(Operand::Loc(0), Operand::Loc(2)).with_both(Operand::Loc(1)));
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -801,7 +1038,24 @@ mod tests {
         let expected = Op::Out(UnOp::new(Operand::Loc(0)).with_first(Operand::Loc(1)));
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
+
+        assert_eq!(actual, expected);
+        assert!(code.is_empty());
+    }
+
+    #[test]
+    fn decode_emp() {
+        let code = [
+            // end emp
+            END,
+            0b1110_0000,
+        ];
+
+        let expected = Op::End(Operand::Emp);
+
+        let mut code = code.as_ref();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -817,7 +1071,7 @@ mod tests {
         let expected = Op::Fls;
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
@@ -837,7 +1091,7 @@ mod tests {
         let expected = Op::Cpy(Operand::Loc(0), Operand::Loc(1), Operand::Val(12));
 
         let mut code = code.as_ref();
-        let actual = decode_op(&mut code).unwrap();
+        let actual = decode_op(&mut code, Endian::Little).unwrap();
 
         assert_eq!(actual, expected);
         assert!(code.is_empty());
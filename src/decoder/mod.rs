@@ -1,4 +1,5 @@
 pub mod decode;
+#[allow(clippy::module_inception)]
 mod decoder;
 
 pub use decoder::*;
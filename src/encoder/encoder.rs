@@ -8,6 +8,24 @@ pub enum EncodeError {
     FailedToWrite,
 }
 
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::WriteError(e) => write!(f, "write error: {}", e),
+            EncodeError::FailedToWrite => write!(f, "failed to write"),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::WriteError(e) => Some(e),
+            EncodeError::FailedToWrite => None,
+        }
+    }
+}
+
 impl From<io::Error> for EncodeError {
     fn from(e: io::Error) -> Self {
         EncodeError::WriteError(e)
@@ -18,7 +36,7 @@ impl ExpectedError for EncodeError {
     const ERROR: Self = EncodeError::FailedToWrite;
 }
 
-fn encode_op<W>(op: Op, buf: &mut W) -> Result<(), EncodeError>
+fn encode_op<W>(op: Op, buf: &mut W, e: Endian) -> Result<(), EncodeError>
 where
     W: Write,
 {
@@ -29,191 +47,211 @@ where
         Nop => NOP.encode(buf),
         End(x) => {
             END.encode(buf)?;
-            x.encode(buf)
+            (x, e).encode(buf)
         }
         Slp(x) => {
             SLP.encode(buf)?;
-            x.encode(buf)
+            (x, e).encode(buf)
         }
         Set(b, t) => {
             SET.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Cnv(x, y, t, u) => {
             CNV.encode(buf)?;
             (t, u).encode(buf)?;
-            x.encode(buf)?;
-            y.encode(buf)
+            (x, e).encode(buf)?;
+            (y, e).encode(buf)
         }
         Add(b, t) => {
             ADD.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Sub(b, t) => {
             SUB.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Mul(b, t) => {
             MUL.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Div(b, t) => {
             DIV.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Mod(b, t) => {
             MOD.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Shl(x, y, t) => {
             SHL.encode(buf)?;
             t.encode(buf)?;
-            x.encode(buf)?;
-            y.encode(buf)
+            (x, e).encode(buf)?;
+            (y, e).encode(buf)
         }
         Shr(x, y, t) => {
             SHR.encode(buf)?;
             t.encode(buf)?;
-            x.encode(buf)?;
-            y.encode(buf)
+            (x, e).encode(buf)?;
+            (y, e).encode(buf)
         }
         And(b, t) => {
             AND.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Or(b, t) => {
             OR.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Xor(b, t) => {
             XOR.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Not(u, t) => {
             NOT.encode(buf)?;
-            (u, t).encode(buf)
+            (u, t, e).encode(buf)
         }
         Neg(u, t) => {
             NEG.encode(buf)?;
-            (u, t).encode(buf)
+            (u, t, e).encode(buf)
         }
         Inc(u, t) => {
             INC.encode(buf)?;
-            (u, t).encode(buf)
+            (u, t, e).encode(buf)
         }
         Dec(u, t) => {
             DEC.encode(buf)?;
-            (u, t).encode(buf)
+            (u, t, e).encode(buf)
+        }
+        Sqt(u, t) => {
+            SQT.encode(buf)?;
+            (u, t, e).encode(buf)
+        }
+        Abs(u, t) => {
+            ABS.encode(buf)?;
+            (u, t, e).encode(buf)
+        }
+        Min(b, t) => {
+            MIN.encode(buf)?;
+            (b, t, e).encode(buf)
+        }
+        Max(b, t) => {
+            MAX.encode(buf)?;
+            (b, t, e).encode(buf)
         }
         Go(x) => {
             GO.encode(buf)?;
-            x.encode(buf)
+            (x, e).encode(buf)
+        }
+        Jmp(x) => {
+            JMP.encode(buf)?;
+            (x, e).encode(buf)
         }
         Ift(u, t) => {
             IFT.encode(buf)?;
-            (u, t).encode(buf)
+            (u, t, e).encode(buf)
         }
         Iff(u, t) => {
             IFF.encode(buf)?;
-            (u, t).encode(buf)
+            (u, t, e).encode(buf)
         }
         Ife(b, t) => {
             IFE.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Ifl(b, t) => {
             IFL.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Ifg(b, t) => {
             IFG.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Ine(b, t) => {
             INE.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Inl(b, t) => {
             INL.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Ing(b, t) => {
             ING.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Ifa(b, t) => {
             IFA.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Ifo(b, t) => {
             IFO.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Ifx(b, t) => {
             IFX.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Ina(b, t) => {
             INA.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Ino(b, t) => {
             INO.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         Inx(b, t) => {
             INX.encode(buf)?;
-            (b, t).encode(buf)
+            (b, t, e).encode(buf)
         }
         App(x) => {
             APP.encode(buf)?;
-            x.encode(buf)
+            (x, e).encode(buf)
         }
         Par(u, t) => {
             PAR.encode(buf)?;
-            (u, t).encode(buf)
+            (u, t, e).encode(buf)
         }
         Clf(x) => {
             CLF.encode(buf)?;
-            x.encode(buf)
+            (x, e).encode(buf)
         }
         Ret(u, t) => {
             RET.encode(buf)?;
-            (u, t).encode(buf)
+            (u, t, e).encode(buf)
         }
         In(b) => {
             IN.encode(buf)?;
-            (b, OpType::U8).encode(buf)
+            (b, OpType::U8, e).encode(buf)
         }
         Out(u) => {
             OUT.encode(buf)?;
-            (u, OpType::U8).encode(buf)
+            (u, OpType::U8, e).encode(buf)
         }
         Fls => FLS.encode(buf),
         Sfd(x) => {
             SFD.encode(buf)?;
-            x.encode(buf)
+            (x, e).encode(buf)
         }
         Gfd(x) => {
             GFD.encode(buf)?;
-            x.encode(buf)
+            (x, e).encode(buf)
         }
         Zer(x, y) => {
             ZER.encode(buf)?;
-            x.encode(buf)?;
-            y.encode(buf)
+            (x, e).encode(buf)?;
+            (y, e).encode(buf)
         }
         Cmp(x, y, z) => {
             CMP.encode(buf)?;
-            x.encode(buf)?;
-            y.encode(buf)?;
-            z.encode(buf)
+            (x, e).encode(buf)?;
+            (y, e).encode(buf)?;
+            (z, e).encode(buf)
         }
         Cpy(x, y, z) => {
             CPY.encode(buf)?;
-            x.encode(buf)?;
-            y.encode(buf)?;
-            z.encode(buf)
+            (x, e).encode(buf)?;
+            (y, e).encode(buf)?;
+            (z, e).encode(buf)
         }
     }
 }
@@ -225,7 +263,19 @@ impl Encode for Op {
     where
         W: Write,
     {
-        encode_op(*self, buf)
+        encode_op(*self, buf, Endian::Little)
+    }
+}
+
+impl Encode for (Op, Endian) {
+    type Err = EncodeError;
+
+    fn encode<W>(&self, buf: &mut W) -> Result<(), Self::Err>
+    where
+        W: Write,
+    {
+        let (op, e) = *self;
+        encode_op(op, buf, e)
     }
 }
 
@@ -243,29 +293,50 @@ impl Encode for u8 {
 impl Encode for Operand {
     type Err = EncodeError;
 
+    fn encode<W>(&self, buf: &mut W) -> Result<(), Self::Err>
+    where
+        W: Write,
+    {
+        (*self, Endian::Little).encode(buf)
+    }
+}
+
+impl Encode for (Operand, Endian) {
+    type Err = EncodeError;
+
     fn encode<W>(&self, buf: &mut W) -> Result<(), Self::Err>
     where
         W: Write,
     {
         const SHORT_MAX_VALUE: UWord = !LONG_OPERAND_BIT as UWord;
 
-        if let Some(val) = self.get() {
-            let bytes = val.to_le_bytes();
+        let (operand, e) = self;
+
+        if let Some(val) = operand.get() {
+            let mut bytes = val.to_le_bytes();
 
-            if val <= SHORT_MAX_VALUE && matches!(self, Operand::Loc(_)) {
+            if val <= SHORT_MAX_VALUE && matches!(operand, Operand::Loc(_)) {
                 return bytes[0].encode(buf);
             }
 
-            let n_bytes = bytes.iter().rev().skip_while(|&b| *b == 0).count();
+            // Zero still takes one byte of the value
+            let n_bytes = bytes.iter().rev().skip_while(|&b| *b == 0).count().max(1);
 
-            let mut meta = self.as_byte() << 4;
+            let mut meta = operand.as_byte() << 4;
             meta |= n_bytes as u8 - 1;
             meta |= LONG_OPERAND_BIT;
 
+            // Only significant bytes are written, so big-endian is their reverse
+            if *e == Endian::Big {
+                bytes[..n_bytes].reverse();
+            }
+
             meta.encode(buf)?;
             buf.write(&bytes[..n_bytes]).expected(n_bytes)
         } else {
-            let operand_meta = self.as_byte() << 4;
+            // The long bit is set to separate an empty operand from a short local
+            let mut operand_meta = operand.as_byte() << 4;
+            operand_meta |= LONG_OPERAND_BIT;
             operand_meta.encode(buf)
         }
     }
@@ -282,18 +353,14 @@ impl Encode for OpType {
     }
 }
 
-impl Encode for (OpType, Variant) {
+impl Encode for Spec {
     type Err = EncodeError;
 
     fn encode<W>(&self, buf: &mut W) -> Result<(), Self::Err>
     where
         W: Write,
     {
-        let (op_type, variant) = self;
-        let mut meta = variant.as_byte() << 6;
-        meta |= op_type.as_byte();
-
-        meta.encode(buf)
+        self.to_byte().encode(buf)
     }
 }
 
@@ -304,10 +371,22 @@ impl Encode for (BinOp, OpType) {
     where
         W: Write,
     {
-        let (bin_op, op_type) = self;
+        let (bin_op, op_type) = *self;
+        (bin_op, op_type, Endian::Little).encode(buf)
+    }
+}
 
-        (*op_type, bin_op.variant()).encode(buf)?;
-        bin_op.encode(buf)
+impl Encode for (BinOp, OpType, Endian) {
+    type Err = EncodeError;
+
+    fn encode<W>(&self, buf: &mut W) -> Result<(), Self::Err>
+    where
+        W: Write,
+    {
+        let (bin_op, op_type, e) = *self;
+
+        Spec::new(op_type, bin_op.variant()).encode(buf)?;
+        (bin_op, e).encode(buf)
     }
 }
 
@@ -318,10 +397,22 @@ impl Encode for (UnOp, OpType) {
     where
         W: Write,
     {
-        let (un_op, op_type) = self;
+        let (un_op, op_type) = *self;
+        (un_op, op_type, Endian::Little).encode(buf)
+    }
+}
+
+impl Encode for (UnOp, OpType, Endian) {
+    type Err = EncodeError;
+
+    fn encode<W>(&self, buf: &mut W) -> Result<(), Self::Err>
+    where
+        W: Write,
+    {
+        let (un_op, op_type, e) = *self;
 
-        (*op_type, un_op.variant()).encode(buf)?;
-        un_op.encode(buf)
+        Spec::new(op_type, un_op.variant()).encode(buf)?;
+        (un_op, e).encode(buf)
     }
 }
 
@@ -347,18 +438,30 @@ impl Encode for BinOp {
     where
         W: Write,
     {
-        let (x, y, offset) = match self {
+        (*self, Endian::Little).encode(buf)
+    }
+}
+
+impl Encode for (BinOp, Endian) {
+    type Err = EncodeError;
+
+    fn encode<W>(&self, buf: &mut W) -> Result<(), Self::Err>
+    where
+        W: Write,
+    {
+        let (bin_op, e) = *self;
+        let (x, y, offset) = match bin_op {
             BinOp::None { x, y } => (x, y, None),
             BinOp::First { x, y, offset } => (x, y, Some(offset)),
             BinOp::Second { x, y, offset } => (x, y, Some(offset)),
             BinOp::Both { x, y, offset } => (x, y, Some(offset)),
         };
 
-        x.encode(buf)?;
-        y.encode(buf)?;
+        (x, e).encode(buf)?;
+        (y, e).encode(buf)?;
 
         if let Some(o) = offset {
-            o.encode(buf)
+            (o, e).encode(buf)
         } else {
             Ok(())
         }
@@ -372,15 +475,27 @@ impl Encode for UnOp {
     where
         W: Write,
     {
-        let (x, offset) = match self {
+        (*self, Endian::Little).encode(buf)
+    }
+}
+
+impl Encode for (UnOp, Endian) {
+    type Err = EncodeError;
+
+    fn encode<W>(&self, buf: &mut W) -> Result<(), Self::Err>
+    where
+        W: Write,
+    {
+        let (un_op, e) = *self;
+        let (x, offset) = match un_op {
             UnOp::None { x } => (x, None),
             UnOp::First { x, offset } => (x, Some(offset)),
         };
 
-        x.encode(buf)?;
+        (x, e).encode(buf)?;
 
         if let Some(o) = offset {
-            o.encode(buf)
+            (o, e).encode(buf)
         } else {
             Ok(())
         }
@@ -397,7 +512,7 @@ mod tests {
         let op = Op::End(Operand::Loc(12));
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[END, 12]);
     }
@@ -407,14 +522,14 @@ mod tests {
         let op = Op::End(Operand::Ind(12));
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[END, 0b1001_0000, 12]);
 
         let op = Op::End(Operand::Val(256));
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[END, 0b1011_0001, 0, 1]);
     }
@@ -424,9 +539,9 @@ mod tests {
         let op = Op::End(Operand::Emp);
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
-        assert_eq!(buf, &[END, 0b0110_0000]);
+        assert_eq!(buf, &[END, 0b1110_0000]);
     }
 
     #[test]
@@ -437,7 +552,7 @@ mod tests {
         );
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[INC, 0b0100_0011, 0b1001_0000, 16, 0b1100_0000, 1]);
     }
@@ -447,7 +562,7 @@ mod tests {
         let op = Op::Set(BinOp::new(Operand::Loc(8), Operand::Loc(16)), OpType::I16);
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[SET, 0b0000_0011, 8, 16]);
     }
@@ -460,7 +575,7 @@ mod tests {
         );
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(
             buf,
@@ -476,7 +591,7 @@ mod tests {
         );
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(
             buf,
@@ -501,7 +616,7 @@ mod tests {
         );
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(
             buf,
@@ -526,7 +641,7 @@ mod tests {
         );
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(
             buf,
@@ -548,7 +663,7 @@ mod tests {
         let op = Op::Cnv(Operand::Loc(12), Operand::Loc(9), OpType::U8, OpType::U16);
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[CNV, 0b0010_0000, 12, 9]);
     }
@@ -558,7 +673,7 @@ mod tests {
         let op = Op::Shl(Operand::Loc(12), Operand::Loc(9), OpType::U32);
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[SHL, 0b0000_0100, 12, 9]);
     }
@@ -571,7 +686,7 @@ mod tests {
         );
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[IFE, 0b0100_0010, 12, 0b1100_0000, 8, 0b1100_0000, 4]);
     }
@@ -581,7 +696,7 @@ mod tests {
         let op = Op::App(Operand::Ref(8));
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[APP, 0b1100_0000, 8]);
     }
@@ -594,7 +709,7 @@ mod tests {
         );
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[PAR, 0b0100_1011, 0b1100_0000, 8, 0b1011_0000, 6]);
     }
@@ -604,7 +719,7 @@ mod tests {
         let op = Op::Ret(UnOp::new(Operand::Loc(16)), OpType::U8);
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[RET, 0b0000_0000, 16]);
     }
@@ -614,7 +729,7 @@ mod tests {
         let op = Op::In(BinOp::new(Operand::Loc(0), Operand::Loc(2)).with_both(Operand::Loc(1)));
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[IN, 0b1100_0000, 0, 2, 1]);
     }
@@ -624,7 +739,7 @@ mod tests {
         let op = Op::Out(UnOp::new(Operand::Loc(0)).with_first(Operand::Loc(1)));
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[OUT, 0b0100_0000, 0, 1]);
     }
@@ -634,7 +749,7 @@ mod tests {
         let op = Op::Fls;
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[FLS]);
     }
@@ -644,8 +759,127 @@ mod tests {
         let op = Op::Cpy(Operand::Loc(0), Operand::Loc(1), Operand::Val(12));
 
         let mut buf = vec![];
-        encode_op(op, &mut buf).unwrap();
+        encode_op(op, &mut buf, Endian::Little).unwrap();
 
         assert_eq!(buf, &[CPY, 0, 1, 0b1011_0000, 12]);
     }
+
+    #[test]
+    fn encode_endian() {
+        use crate::decoder::decode::decode_with;
+
+        let op = Op::Set(
+            BinOp::new(Operand::Glb(0x01_0203), Operand::Val(0x1234)),
+            OpType::U16,
+        );
+
+        let little = [
+            SET,
+            0b0000_0010,
+            0b1101_0010,
+            3,
+            2,
+            1,
+            0b1011_0001,
+            0x34,
+            0x12,
+        ];
+        let big = [
+            SET,
+            0b0000_0010,
+            0b1101_0010,
+            1,
+            2,
+            3,
+            0b1011_0001,
+            0x12,
+            0x34,
+        ];
+
+        for (e, expected) in [(Endian::Little, &little), (Endian::Big, &big)] {
+            let mut buf = vec![];
+            (op, e).encode(&mut buf).unwrap();
+            assert_eq!(&buf, expected);
+
+            let decoded: Op = decode_with(&mut buf.as_slice(), e).unwrap();
+            assert_eq!(decoded, op);
+        }
+
+        let mut buf = vec![];
+        op.encode(&mut buf).unwrap();
+        assert_eq!(buf, little);
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        use crate::decoder::decode::decode;
+
+        let ops = [
+            Op::Nop,
+            Op::End(Operand::Val(0)),
+            Op::End(Operand::Emp),
+            Op::Slp(Operand::Ind(0)),
+            Op::Set(BinOp::new(Operand::Loc(96), Operand::Emp), OpType::U8),
+            Op::Set(BinOp::new(Operand::Loc(127), Operand::Loc(128)), OpType::I8),
+            Op::Cnv(Operand::Glb(4), Operand::Ret(0), OpType::F64, OpType::Iw),
+            Op::Add(
+                BinOp::new(Operand::Loc(0), Operand::Val(UWord::MAX)),
+                OpType::Uw,
+            ),
+            Op::Sub(
+                BinOp::new(Operand::Ret(8), Operand::Ref(16)).with_first(Operand::Val(5)),
+                OpType::I16,
+            ),
+            Op::Mul(
+                BinOp::new(Operand::Ret(8), Operand::Ref(16)).with_second(Operand::Loc(5)),
+                OpType::U64,
+            ),
+            Op::Div(
+                BinOp::new(Operand::Ret(8), Operand::Ref(16)).with_both(Operand::Glb(5)),
+                OpType::F32,
+            ),
+            Op::Shr(Operand::Loc(12), Operand::Val(3), OpType::I64),
+            Op::Not(UnOp::new(Operand::Loc(1)), OpType::U16),
+            Op::Neg(
+                UnOp::new(Operand::Ind(16)).with_first(Operand::Ref(1)),
+                OpType::I32,
+            ),
+            Op::Sqt(UnOp::new(Operand::Loc(8)), OpType::F64),
+            Op::Abs(UnOp::new(Operand::Loc(8)), OpType::I8),
+            Op::Min(
+                BinOp::new(Operand::Loc(0), Operand::Loc(4)).with_second(Operand::Val(1)),
+                OpType::F32,
+            ),
+            Op::Max(BinOp::new(Operand::Loc(0), Operand::Val(7)), OpType::U32),
+            Op::Go(Operand::Val(300)),
+            Op::Jmp(Operand::Val(-2 as IWord as UWord)),
+            Op::Iff(UnOp::new(Operand::Loc(2)), OpType::U32),
+            Op::Inx(BinOp::new(Operand::Loc(0), Operand::Loc(1)), OpType::U8),
+            Op::App(Operand::Val(1)),
+            Op::Par(UnOp::new(Operand::Loc(4)), OpType::Uw),
+            Op::Clf(Operand::Ref(0)),
+            Op::Ret(UnOp::new(Operand::Emp), OpType::U8),
+            Op::In(BinOp::new(Operand::Loc(0), Operand::Emp)),
+            Op::Out(UnOp::new(Operand::Loc(0)).with_first(Operand::Loc(1))),
+            Op::Fls,
+            Op::Sfd(Operand::Val(0)),
+            Op::Gfd(Operand::Loc(0)),
+            Op::Zer(Operand::Ref(0), Operand::Val(16)),
+            Op::Cmp(Operand::Ref(0), Operand::Ref(8), Operand::Val(8)),
+            Op::Cpy(Operand::Ref(0), Operand::Ref(8), Operand::Val(8)),
+        ];
+
+        let mut buf = vec![];
+        for op in ops.iter() {
+            op.encode(&mut buf).unwrap();
+        }
+
+        let mut code = buf.as_slice();
+        for op in ops.iter() {
+            let actual: Op = decode(&mut code).unwrap();
+            assert_eq!(actual, *op);
+        }
+
+        assert!(code.is_empty());
+    }
 }
@@ -1,4 +1,5 @@
 pub mod encode;
+#[allow(clippy::module_inception)]
 mod encoder;
 
 pub use encoder::*;
@@ -0,0 +1,221 @@
+use super::{
+    debugger::{Debugger, Location},
+    ExecutionError, Function,
+};
+use crate::common::{Op, UWord};
+use std::{cell::RefCell, collections::BTreeMap, fmt, rc::Rc};
+
+/// Outcomes of a conditional operation.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Branch {
+    /// The condition held and the next operation was executed.
+    pub taken: u64,
+
+    /// The condition failed and the next operation was passed.
+    pub passed: u64,
+}
+
+impl Branch {
+    /// Checks whether both outcomes were observed.
+    pub fn is_covered(&self) -> bool {
+        self.taken != 0 && self.passed != 0
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FunctionCoverage {
+    /// Execution count of each operation.
+    pub hits: Vec<u64>,
+
+    /// Outcomes of each conditional operation by its index.
+    pub branches: BTreeMap<UWord, Branch>,
+}
+
+impl FunctionCoverage {
+    /// Number of operations executed at least once.
+    pub fn executed(&self) -> usize {
+        self.hits.iter().filter(|&&n| n != 0).count()
+    }
+
+    /// Total number of operations.
+    pub fn len(&self) -> usize {
+        self.hits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+}
+
+/// Coverage of a set of functions, indexed by the function id.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CoverageReport {
+    pub functions: Vec<FunctionCoverage>,
+}
+
+impl fmt::Display for CoverageReport {
+    /// Writes a summary line of each function followed by
+    /// tab separated lines of `op hits [taken passed]`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (id, function) in self.functions.iter().enumerate() {
+            let outcomes: usize = function
+                .branches
+                .values()
+                .map(|b| (b.taken != 0) as usize + (b.passed != 0) as usize)
+                .sum();
+
+            writeln!(
+                f,
+                "function {}: {}/{} ops, {}/{} branch outcomes",
+                id,
+                function.executed(),
+                function.len(),
+                outcomes,
+                function.branches.len() * 2,
+            )?;
+
+            for (op, hits) in function.hits.iter().enumerate() {
+                write!(f, "{}\t{}", op, hits)?;
+                if let Some(branch) = function.branches.get(&(op as UWord)) {
+                    write!(f, "\t{}\t{}", branch.taken, branch.passed)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A debugger collecting execution coverage.
+///
+/// The collector is a shared handle: attach a clone to the executor
+/// and take the report from the other one.
+#[derive(Clone, Debug, Default)]
+pub struct Coverage {
+    report: Rc<RefCell<CoverageReport>>,
+    conditional: Rc<RefCell<Option<Location>>>,
+}
+
+impl Coverage {
+    pub fn new(functions: &[Function]) -> Self {
+        let functions = functions
+            .iter()
+            .map(|function| {
+                let program = function.program();
+                FunctionCoverage {
+                    hits: vec![0; program.len()],
+                    branches: program
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, op)| op.is_conditional())
+                        .map(|(idx, _)| (idx as UWord, Branch::default()))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Self {
+            report: Rc::new(RefCell::new(CoverageReport { functions })),
+            conditional: Rc::default(),
+        }
+    }
+
+    /// Returns the coverage collected so far.
+    pub fn report(&self) -> CoverageReport {
+        self.report.borrow().clone()
+    }
+}
+
+impl Debugger for Coverage {
+    fn on_step(&mut self, location: Location, op: &Op) {
+        let mut report = self.report.borrow_mut();
+        let function = match report.functions.get_mut(location.function as usize) {
+            Some(function) => function,
+            None => return,
+        };
+
+        // The outcome of the previous conditional is known by the operation executed after
+        if let Some(cond) = self.conditional.borrow_mut().take() {
+            if cond.function == location.function {
+                if let Some(branch) = function.branches.get_mut(&cond.op) {
+                    if location.op == cond.op.wrapping_add(1) {
+                        branch.taken += 1;
+                    } else {
+                        branch.passed += 1;
+                    }
+                }
+            }
+        }
+
+        if let Some(hits) = function.hits.get_mut(location.op as usize) {
+            *hits += 1;
+        }
+
+        if op.is_conditional() {
+            *self.conditional.borrow_mut() = Some(location);
+        }
+    }
+
+    fn on_trap(&mut self, location: Location, _: ExecutionError) {
+        let mut conditional = self.conditional.borrow_mut();
+        if *conditional == Some(location) {
+            *conditional = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{BinOp, OpType, Operand, UnOp},
+        executor::{Executed, ExecutionSuccess, Executor},
+    };
+
+    #[test]
+    fn coverage_report() {
+        let program = [
+            Op::Ife(BinOp::new(Operand::Loc(0), Operand::Val(0)), OpType::U8),
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::U8),
+            Op::Ife(BinOp::new(Operand::Loc(0), Operand::Val(0)), OpType::U8),
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::U8),
+            Op::End(Operand::Val(0)),
+        ];
+        let functions = [Function::new(1, &program)];
+
+        let coverage = Coverage::new(&functions);
+        let mut exe = Executor::new(&functions);
+        exe.call(0, 0).unwrap();
+        exe.attach(coverage.clone());
+        assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::End(0)));
+
+        let report = coverage.report();
+        let function = &report.functions[0];
+        assert_eq!(function.hits, [1, 1, 1, 0, 1]);
+        assert_eq!(function.executed(), 4);
+        assert_eq!(
+            function.branches.values().copied().collect::<Vec<_>>(),
+            [
+                Branch {
+                    taken: 1,
+                    passed: 0
+                },
+                Branch {
+                    taken: 0,
+                    passed: 1
+                },
+            ]
+        );
+
+        assert_eq!(
+            report.to_string(),
+            "function 0: 4/5 ops, 2/4 branch outcomes\n\
+             0\t1\t1\t0\n\
+             1\t1\n\
+             2\t1\t0\t1\n\
+             3\t0\n\
+             4\t1\n"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use super::ExecutionError;
+use crate::common::{Op, UWord};
+use std::ops::Range;
+
+/// Position of an operation: the function index and the operation index in it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Location {
+    pub function: UWord,
+    pub op: UWord,
+}
+
+/// A data watchpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Watch {
+    /// Local bytes of the frame the operation is executed in.
+    Local(Range<UWord>),
+
+    /// Absolute memory addresses.
+    Memory(Range<UWord>),
+}
+
+/// Hooks invoked by the executor when the debugger is attached.
+pub trait Debugger: std::fmt::Debug {
+    /// Called before the operation is executed.
+    fn on_step(&mut self, _location: Location, _op: &Op) {}
+
+    /// Called when the operation fails.
+    fn on_trap(&mut self, _location: Location, _error: ExecutionError) {}
+
+    /// Checks whether the execution should break before the operation.
+    fn is_breakpoint(&self, _location: Location) -> bool {
+        false
+    }
+
+    /// Data watchpoints checked around every operation.
+    fn watches(&self) -> &[Watch] {
+        &[]
+    }
+
+    /// Called when the operation changes the watched bytes.
+    /// The execution breaks after the operation.
+    fn on_watch(&mut self, _location: Location, _watch: &Watch, _old: &[u8], _new: &[u8]) {}
+}
@@ -2,11 +2,18 @@
 mod tests;
 
 use super::{
+    debugger::{Debugger, Location, Watch},
     files::{Files, FilesError},
     memory::*,
     primary::*,
 };
 use crate::common::*;
+use std::{
+    future::Future,
+    ops::Range,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 #[derive(Debug)]
 pub struct Function<'f> {
@@ -14,8 +21,26 @@ pub struct Function<'f> {
     program: &'f [Op],
 }
 
-#[derive(Debug)]
+impl<'f> Function<'f> {
+    pub fn new(frame_size: UWord, program: &'f [Op]) -> Self {
+        Self {
+            frame_size,
+            program,
+        }
+    }
+
+    pub fn frame_size(&self) -> UWord {
+        self.frame_size
+    }
+
+    pub fn program(&self) -> &'f [Op] {
+        self.program
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct FunctionCall<'f> {
+    id: UWord,
     function: &'f Function<'f>,
     base_ptr: UWord,
     ret_val_ptr: UWord,
@@ -32,6 +57,7 @@ pub enum ExecutionError {
     OperationOverflow,
     DivisionByZero,
     NullPointerDereference,
+    OutOfFuel,
 }
 
 impl From<MemoryError> for ExecutionError {
@@ -46,15 +72,53 @@ impl From<FilesError> for ExecutionError {
     }
 }
 
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ExecutionError::*;
+
+        match self {
+            EndOfProgram => write!(f, "end of program"),
+            MemoryError(e) => e.fmt(f),
+            FilesError(e) => e.fmt(f),
+            IncorrectOperation(op) => write!(f, "incorrect operation `{}`", op),
+            UnknownFunction(id) => write!(f, "unknown function {}", id),
+            OperationOverflow => write!(f, "operation overflow"),
+            DivisionByZero => write!(f, "division by zero"),
+            NullPointerDereference => write!(f, "null pointer dereference"),
+            OutOfFuel => write!(f, "out of fuel"),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+impl From<ExecutionError> for std::io::Error {
+    fn from(e: ExecutionError) -> Self {
+        std::io::Error::other(e)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum ExecutionSuccess {
     Ok,
     End(UWord),
     Sleep(UWord),
+    Break,
 }
 
 pub type Executed = Result<ExecutionSuccess, ExecutionError>;
 
+/// Saved state of an executor: memory, call stack and fuel. Files and the debugger are not saved.
+#[derive(Clone, Debug)]
+pub struct Snapshot<'f> {
+    memory: Memory,
+    program_counter: UWord,
+    call_stack: Vec<FunctionCall<'f>>,
+    prepared_call: bool,
+    parameter_ptr: UWord,
+    fuel: Option<u64>,
+}
+
 #[derive(Debug)]
 pub struct Executor<'f> {
     functions: &'f [Function<'f>],
@@ -64,6 +128,9 @@ pub struct Executor<'f> {
     prepared_call: bool,
     parameter_ptr: UWord,
     files: Files,
+    fuel: Option<u64>,
+    debugger: Option<Box<dyn Debugger>>,
+    break_passed: bool,
 }
 
 macro_rules! impl_cnv {
@@ -102,6 +169,9 @@ impl<'f> Executor<'f> {
             prepared_call: false,
             parameter_ptr: 0,
             files: Files::new(),
+            fuel: None,
+            debugger: None,
+            break_passed: false,
         }
     }
 
@@ -112,6 +182,7 @@ impl<'f> Executor<'f> {
             .ok_or(ExecutionError::UnknownFunction(function_id))?;
 
         self.call_stack.push(FunctionCall {
+            id: function_id,
             function: f,
             base_ptr: self.memory.stack.len(),
             ret_val_ptr: 0,
@@ -144,6 +215,21 @@ impl<'f> Executor<'f> {
         self.clf(ret_val_ptr)
     }
 
+    pub fn files(&mut self) -> &mut Files {
+        &mut self.files
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Grows the heap by `size` zeroed bytes and returns the address of the first new byte.
+    pub fn grow_heap(&mut self, size: UWord) -> Result<UWord, ExecutionError> {
+        let ptr = Memory::HEAP_BASE + self.memory.heap.len();
+        self.memory.heap.expand(size)?;
+        Ok(ptr)
+    }
+
     fn ret(&mut self) -> Result<(), ExecutionError> {
         let current_fn = self.call_stack.pop().ok_or(ExecutionError::EndOfProgram)?;
 
@@ -153,7 +239,7 @@ impl<'f> Executor<'f> {
         Ok(())
     }
 
-    fn current_call(&self) -> Result<&FunctionCall, ExecutionError> {
+    fn current_call(&self) -> Result<&FunctionCall<'_>, ExecutionError> {
         let call = if self.prepared_call {
             self.call_stack.get(self.call_stack.len().wrapping_sub(2))
         } else {
@@ -215,7 +301,7 @@ impl<'f> Executor<'f> {
     where
         T: Primary,
     {
-        Ok(match operand {
+        match operand {
             Operand::Loc(loc) => self
                 .memory
                 .set(self.current_call()?.base_ptr.wrapping_add(loc), val)?,
@@ -237,7 +323,9 @@ impl<'f> Executor<'f> {
             Operand::Ref(_) => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
             Operand::Glb(ptr) => self.memory.set(ptr, val)?,
             Operand::Emp => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
-        })
+        }
+
+        Ok(())
     }
 
     fn read_un_operand(&self, un: UnOp) -> Result<Operand, ExecutionError> {
@@ -431,6 +519,34 @@ impl<'f> Executor<'f> {
         self.update_un::<T, T, _>(un, |x| x.wrapping())
     }
 
+    fn exec_sqt<T>(&mut self, un: UnOp) -> Result<(), ExecutionError>
+    where
+        T: Sqrt,
+    {
+        self.update_un::<T, T, _>(un, |x| x.sqrt())
+    }
+
+    fn exec_abs<T>(&mut self, un: UnOp) -> Result<(), ExecutionError>
+    where
+        T: Abs,
+    {
+        self.update_un::<T, T, _>(un, |x| x.wrapping())
+    }
+
+    fn exec_min<T>(&mut self, bin: BinOp) -> Result<(), ExecutionError>
+    where
+        T: MinMax,
+    {
+        self.update_bin::<T, T, _>(bin, |x, y| x.minimum(y))
+    }
+
+    fn exec_max<T>(&mut self, bin: BinOp) -> Result<(), ExecutionError>
+    where
+        T: MinMax,
+    {
+        self.update_bin::<T, T, _>(bin, |x, y| x.maximum(y))
+    }
+
     fn exec_ife<T>(&self, bin: BinOp) -> Result<bool, ExecutionError>
     where
         T: Primary + PartialEq,
@@ -467,16 +583,24 @@ impl<'f> Executor<'f> {
     where
         T: Primary + PartialOrd,
     {
+        // Negation of `ifl` rather than `>=`, so it also holds for NaN
         let (left, right) = self.read_bin_operands(bin)?;
-        Ok(self.get_val::<T>(left)? >= self.get_val::<T>(right)?)
+        let ord = self
+            .get_val::<T>(left)?
+            .partial_cmp(&self.get_val::<T>(right)?);
+        Ok(ord != Some(std::cmp::Ordering::Less))
     }
 
     fn exec_ing<T>(&self, bin: BinOp) -> Result<bool, ExecutionError>
     where
         T: Primary + PartialOrd,
     {
+        // Negation of `ifg` rather than `<=`, so it also holds for NaN
         let (left, right) = self.read_bin_operands(bin)?;
-        Ok(self.get_val::<T>(left)? <= self.get_val::<T>(right)?)
+        let ord = self
+            .get_val::<T>(left)?
+            .partial_cmp(&self.get_val::<T>(right)?);
+        Ok(ord != Some(std::cmp::Ordering::Greater))
     }
 
     fn exec_ifa<T>(&self, bin: BinOp) -> Result<bool, ExecutionError>
@@ -549,7 +673,184 @@ impl<'f> Executor<'f> {
         self.set_val::<T>(Operand::Ret(0), self.get_val(right)?)
     }
 
+    /// Limits the number of operations to execute. Every executed operation costs one unit of fuel,
+    /// an operation that traps costs nothing.
+    ///
+    /// `None` removes the limit.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    pub fn snapshot(&self) -> Snapshot<'f> {
+        Snapshot {
+            memory: self.memory.clone(),
+            program_counter: self.program_counter,
+            call_stack: self.call_stack.clone(),
+            prepared_call: self.prepared_call,
+            parameter_ptr: self.parameter_ptr,
+            fuel: self.fuel,
+        }
+    }
+
+    /// Restores the state saved by `snapshot`.
+    pub fn restore(&mut self, snapshot: &Snapshot<'f>) {
+        self.memory = snapshot.memory.clone();
+        self.program_counter = snapshot.program_counter;
+        self.call_stack = snapshot.call_stack.clone();
+        self.prepared_call = snapshot.prepared_call;
+        self.parameter_ptr = snapshot.parameter_ptr;
+        self.fuel = snapshot.fuel;
+        self.break_passed = false;
+    }
+
+    pub fn attach<D>(&mut self, debugger: D)
+    where
+        D: Debugger + 'static,
+    {
+        self.debugger = Some(Box::new(debugger));
+        self.break_passed = false;
+    }
+
+    pub fn detach(&mut self) -> Option<Box<dyn Debugger>> {
+        self.debugger.take()
+    }
+
+    /// Executes operations until the program ends, sleeps or breaks.
+    pub fn run(&mut self) -> Executed {
+        loop {
+            match self.execute()? {
+                ExecutionSuccess::Ok => continue,
+                success => break Ok(success),
+            }
+        }
+    }
+
+    /// Runs like `run`, but as a future that yields after every `slice` operations.
+    ///
+    /// It completes when the program ends, sleeps, breaks or fails, so an async host
+    /// can await its own timer on `Sleep` and poll a new future to resume.
+    pub fn run_async(&mut self, slice: u64) -> Run<'_, 'f> {
+        Run {
+            executor: self,
+            slice: slice.max(1),
+        }
+    }
+
     pub fn execute(&mut self) -> Executed {
+        let mut debugger = match self.debugger.take() {
+            Some(debugger) => debugger,
+            None => return self.execute_op(),
+        };
+
+        let res = self.debug_op(debugger.as_mut());
+        self.debugger = Some(debugger);
+        res
+    }
+
+    /// Location of the next operation. After an error it's the operation that failed.
+    pub fn location(&self) -> Option<Location> {
+        let call = self.current_call().ok()?;
+
+        Some(Location {
+            function: call.id,
+            op: self.program_counter,
+        })
+    }
+
+    fn debug_op(&mut self, debugger: &mut dyn Debugger) -> Executed {
+        let location = self.location().ok_or(ExecutionError::EndOfProgram)?;
+
+        // The breakpoint is passed when the execution continues
+        if !self.break_passed && debugger.is_breakpoint(location) {
+            self.break_passed = true;
+            return Ok(ExecutionSuccess::Break);
+        }
+
+        // An operation that can't run isn't shown to the debugger
+        self.check_fuel()?;
+
+        self.break_passed = false;
+        if let Ok(op) = self.current_op() {
+            debugger.on_step(location, op);
+        }
+
+        // Watched bytes are resolved before the operation, since it can change the frame
+        let watched: Vec<_> = debugger
+            .watches()
+            .iter()
+            .map(|watch| {
+                let range = self.watch_range(watch);
+                let old = self.watched_bytes(&range);
+                (watch.clone(), range, old)
+            })
+            .collect();
+
+        let res = self.execute_op();
+        if let Err(e) = res {
+            debugger.on_trap(location, e);
+        }
+
+        let mut hit = false;
+        for (watch, range, old) in watched {
+            if let (Some(old), Some(new)) = (old, self.watched_bytes(&range)) {
+                if old != new {
+                    debugger.on_watch(location, &watch, &old, &new);
+                    hit = true;
+                }
+            }
+        }
+
+        match res {
+            Ok(ExecutionSuccess::Ok) if hit => Ok(ExecutionSuccess::Break),
+            _ => res,
+        }
+    }
+
+    fn watch_range(&self, watch: &Watch) -> Range<UWord> {
+        match watch {
+            Watch::Local(range) => {
+                let base = self.current_call().map(|call| call.base_ptr).unwrap_or(0);
+                base.wrapping_add(range.start)..base.wrapping_add(range.end)
+            }
+            Watch::Memory(range) => range.clone(),
+        }
+    }
+
+    fn watched_bytes(&self, range: &Range<UWord>) -> Option<Vec<u8>> {
+        let size = range.end.checked_sub(range.start)?;
+        self.memory
+            .bytes(range.start, size)
+            .ok()
+            .map(<[u8]>::to_vec)
+    }
+
+    /// Fails when no fuel is left for the next operation.
+    fn check_fuel(&self) -> Result<(), ExecutionError> {
+        match self.fuel {
+            Some(0) => Err(ExecutionError::OutOfFuel),
+            _ => Ok(()),
+        }
+    }
+
+    fn execute_op(&mut self) -> Executed {
+        self.check_fuel()?;
+
+        // A trapped operation can be executed again, so only a done one costs fuel
+        let res = self.run_op();
+        if res.is_ok() {
+            if let Some(fuel) = &mut self.fuel {
+                *fuel -= 1;
+            }
+        }
+
+        res
+    }
+
+    fn run_op(&mut self) -> Executed {
         use Op::*;
         use OpType::*;
 
@@ -853,10 +1154,87 @@ impl<'f> Executor<'f> {
 
                 Ok(ExecutionSuccess::Ok)
             }
+            Sqt(un, ot) => {
+                match ot {
+                    U8 => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    I8 => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    U16 => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    I16 => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    U32 => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    I32 => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    U64 => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    I64 => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    Uw => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    Iw => return Err(ExecutionError::IncorrectOperation(*self.current_op()?)),
+                    F32 => self.exec_sqt::<f32>(un)?,
+                    F64 => self.exec_sqt::<f64>(un)?,
+                }
+
+                Ok(ExecutionSuccess::Ok)
+            }
+            Abs(un, ot) => {
+                match ot {
+                    U8 => self.exec_abs::<u8>(un)?,
+                    I8 => self.exec_abs::<i8>(un)?,
+                    U16 => self.exec_abs::<u16>(un)?,
+                    I16 => self.exec_abs::<i16>(un)?,
+                    U32 => self.exec_abs::<u32>(un)?,
+                    I32 => self.exec_abs::<i32>(un)?,
+                    U64 => self.exec_abs::<u64>(un)?,
+                    I64 => self.exec_abs::<i64>(un)?,
+                    Uw => self.exec_abs::<UWord>(un)?,
+                    Iw => self.exec_abs::<IWord>(un)?,
+                    F32 => self.exec_abs::<f32>(un)?,
+                    F64 => self.exec_abs::<f64>(un)?,
+                }
+
+                Ok(ExecutionSuccess::Ok)
+            }
+            Min(bin, ot) => {
+                match ot {
+                    U8 => self.exec_min::<u8>(bin)?,
+                    I8 => self.exec_min::<i8>(bin)?,
+                    U16 => self.exec_min::<u16>(bin)?,
+                    I16 => self.exec_min::<i16>(bin)?,
+                    U32 => self.exec_min::<u32>(bin)?,
+                    I32 => self.exec_min::<i32>(bin)?,
+                    U64 => self.exec_min::<u64>(bin)?,
+                    I64 => self.exec_min::<i64>(bin)?,
+                    Uw => self.exec_min::<UWord>(bin)?,
+                    Iw => self.exec_min::<IWord>(bin)?,
+                    F32 => self.exec_min::<f32>(bin)?,
+                    F64 => self.exec_min::<f64>(bin)?,
+                }
+
+                Ok(ExecutionSuccess::Ok)
+            }
+            Max(bin, ot) => {
+                match ot {
+                    U8 => self.exec_max::<u8>(bin)?,
+                    I8 => self.exec_max::<i8>(bin)?,
+                    U16 => self.exec_max::<u16>(bin)?,
+                    I16 => self.exec_max::<i16>(bin)?,
+                    U32 => self.exec_max::<u32>(bin)?,
+                    I32 => self.exec_max::<i32>(bin)?,
+                    U64 => self.exec_max::<u64>(bin)?,
+                    I64 => self.exec_max::<i64>(bin)?,
+                    Uw => self.exec_max::<UWord>(bin)?,
+                    Iw => self.exec_max::<IWord>(bin)?,
+                    F32 => self.exec_max::<f32>(bin)?,
+                    F64 => self.exec_max::<f64>(bin)?,
+                }
+
+                Ok(ExecutionSuccess::Ok)
+            }
             Go(x) => {
                 self.program_counter = self.get_val(x)?;
                 return Ok(ExecutionSuccess::Ok);
             }
+            Jmp(x) => {
+                let offset: IWord = self.get_val(x)?;
+                self.program_counter = self.program_counter.wrapping_add(offset as UWord);
+                return Ok(ExecutionSuccess::Ok);
+            }
             Ift(un, ot) => {
                 let res = match ot {
                     U8 => self.get_un::<u8>(un)? != 0,
@@ -1288,3 +1666,26 @@ impl<'f> Executor<'f> {
         res
     }
 }
+
+/// Future returned by `Executor::run_async`.
+#[derive(Debug)]
+pub struct Run<'e, 'f> {
+    executor: &'e mut Executor<'f>,
+    slice: u64,
+}
+
+impl Future for Run<'_, '_> {
+    type Output = Executed;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        for _ in 0..self.slice {
+            match self.executor.execute() {
+                Ok(ExecutionSuccess::Ok) => continue,
+                res => return Poll::Ready(res),
+            }
+        }
+
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
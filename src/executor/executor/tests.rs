@@ -264,6 +264,27 @@ fn executor_go() {
     assert_eq!(exe.get_val::<u32>(Operand::Loc(0)), Ok(3));
 }
 
+#[test]
+fn executor_jmp() {
+    let functions = [Function {
+        frame_size: 1,
+        program: &[
+            Op::Jmp(Operand::Val(2)),
+            Op::End(Operand::Val(1)),
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::U8),
+            Op::Ifl(BinOp::new(Operand::Loc(0), Operand::Val(3)), OpType::U8),
+            Op::Jmp(Operand::Val(-2 as IWord as UWord)),
+            Op::End(Operand::Val(0)),
+        ],
+    }];
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+
+    assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::End(0)));
+    assert_eq!(exe.get_val::<u8>(Operand::Loc(0)), Ok(3));
+}
+
 #[test]
 fn executor_ift() {
     let functions = [Function {
@@ -330,6 +351,32 @@ fn executor_ife() {
     assert_eq!(exe.get_val::<u32>(Operand::Loc(0)), Ok(1));
 }
 
+#[test]
+fn executor_nan_comparison() {
+    let nan = UWord::from_slice(f32::NAN.to_le_bytes().as_ref());
+    let one = UWord::from_slice(1_f32.to_le_bytes().as_ref());
+    let bin = BinOp::new(Operand::Val(nan), Operand::Val(one));
+
+    let cases = [
+        (Op::Ife(bin, OpType::F32), 0),
+        (Op::Ifl(bin, OpType::F32), 0),
+        (Op::Ifg(bin, OpType::F32), 0),
+        (Op::Ine(bin, OpType::F32), 1),
+        (Op::Inl(bin, OpType::F32), 1),
+        (Op::Ing(bin, OpType::F32), 1),
+    ];
+
+    for &(op, expected) in cases.iter() {
+        let program = [op, Op::End(Operand::Val(1)), Op::End(Operand::Val(0))];
+        let functions = [Function::new(0, &program)];
+
+        let mut exe = Executor::new(&functions);
+        exe.call(0, 0).unwrap();
+
+        assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::End(expected)));
+    }
+}
+
 #[test]
 fn executor_ifa() {
     let functions = [Function {
@@ -420,6 +467,40 @@ fn executor_call_fn() {
     assert!(exe.call_stack.is_empty());
 }
 
+#[test]
+fn executor_par_layout() {
+    let functions = [
+        Function {
+            frame_size: 0,
+            program: &[
+                Op::App(Operand::Val(1)),
+                Op::Par(UnOp::new(Operand::Val(1)), OpType::U8),
+                Op::Par(UnOp::new(Operand::Val(2)), OpType::U16),
+                Op::Par(UnOp::new(Operand::Val(3)), OpType::U32),
+                Op::Clf(Operand::Val(0)),
+                Op::Ret(UnOp::new(Operand::Emp), OpType::U8),
+            ],
+        },
+        Function {
+            frame_size: 7,
+            program: &[Op::Ret(UnOp::new(Operand::Emp), OpType::U8)],
+        },
+    ];
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+
+    for _ in 0..5 {
+        assert_eq!(exe.execute(), Executed::Ok(ExecutionSuccess::Ok));
+    }
+
+    // Parameters are packed into the callee locals in order, without alignment
+    assert_eq!(exe.call_stack.len(), 2);
+    assert_eq!(exe.get_val::<u8>(Operand::Loc(0)), Ok(1));
+    assert_eq!(exe.get_val::<u16>(Operand::Loc(1)), Ok(2));
+    assert_eq!(exe.get_val::<u32>(Operand::Loc(3)), Ok(3));
+}
+
 #[test]
 fn executor_glb() {
     let functions = [
@@ -712,3 +793,488 @@ fn executor_cpy() {
     assert_eq!(exe.get_val::<u32>(Operand::Loc(0)), Ok(0x10EF));
     assert_eq!(exe.get_val::<u32>(Operand::Loc(4)), Ok(0x10EF));
 }
+
+#[test]
+fn executor_run() {
+    let program = [
+        Op::Set(BinOp::new(Operand::Loc(0), Operand::Val(3)), OpType::Uw),
+        Op::Slp(Operand::Loc(0)),
+        Op::Dec(UnOp::new(Operand::Loc(0)), OpType::Uw),
+        Op::Ift(UnOp::new(Operand::Loc(0)), OpType::Uw),
+        Op::Go(Operand::Val(2)),
+        Op::End(Operand::Val(7)),
+    ];
+    let functions = [Function::new(
+        std::mem::size_of::<UWord>() as UWord,
+        &program,
+    )];
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+
+    assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::Sleep(3)));
+    assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::End(7)));
+    assert_eq!(exe.get_val::<UWord>(Operand::Loc(0)), Ok(0));
+}
+
+#[test]
+fn executor_call_decoded() {
+    use crate::{assembler::assemble, decoder::disassemble, encoder::encode::Encode};
+
+    let main = assemble(
+        r#"
+        set u32 loc(8) 6
+        app 1
+        par u32 loc(8)
+        par u32 7
+        clf ref(0)
+        end loc(0)
+        "#,
+    )
+    .unwrap();
+
+    let mul = assemble(
+        r#"
+        set u32 ^0 loc(0)
+        mul u32 ^0 loc(4)
+        ret u8 emp
+        "#,
+    )
+    .unwrap();
+
+    let round_trip = |ops: &[Op]| -> Vec<Op> {
+        let mut code = vec![];
+        for op in ops {
+            op.encode(&mut code).unwrap();
+        }
+
+        disassemble(&code)
+            .map(|line| assemble(&line.unwrap()).unwrap()[0])
+            .collect()
+    };
+
+    let main = round_trip(&main);
+    let mul = round_trip(&mul);
+    let functions = [Function::new(12, &main), Function::new(8, &mul)];
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+
+    assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::End(42)));
+}
+
+#[test]
+fn executor_fuel() {
+    let functions = [Function {
+        frame_size: 1,
+        program: &[
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::U8),
+            Op::Jmp(Operand::Val(-1 as IWord as UWord)),
+        ],
+    }];
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+    exe.set_fuel(Some(5));
+
+    assert_eq!(exe.run(), Executed::Err(ExecutionError::OutOfFuel));
+    assert_eq!(exe.fuel(), Some(0));
+    assert_eq!(exe.get_val::<u8>(Operand::Loc(0)), Ok(3));
+
+    exe.set_fuel(Some(1));
+    assert_eq!(exe.execute(), Executed::Ok(ExecutionSuccess::Ok));
+    assert_eq!(exe.get_val::<u8>(Operand::Loc(0)), Ok(3));
+    assert_eq!(exe.execute(), Executed::Err(ExecutionError::OutOfFuel));
+}
+
+#[test]
+fn executor_trap_costs_no_fuel() {
+    let functions = [Function {
+        frame_size: 0,
+        program: &[
+            Op::Inc(UnOp::new(Operand::Glb(Memory::HEAP_BASE)), OpType::U8),
+            Op::End(Operand::Val(0)),
+        ],
+    }];
+
+    let mut exe = Executor::from_limits(&functions, 16, 8);
+    exe.call(0, 0).unwrap();
+    exe.set_fuel(Some(1));
+
+    let fault = MemoryError::SegmentationFault(0, 1);
+    assert_eq!(exe.execute(), Err(ExecutionError::MemoryError(fault)));
+    assert_eq!(exe.fuel(), Some(1));
+
+    exe.grow_heap(1).unwrap();
+    assert_eq!(exe.execute(), Executed::Ok(ExecutionSuccess::Ok));
+    assert_eq!(exe.fuel(), Some(0));
+    assert_eq!(exe.execute(), Executed::Err(ExecutionError::OutOfFuel));
+}
+
+#[test]
+fn executor_grow_heap() {
+    let functions = [Function {
+        frame_size: 0,
+        program: &[
+            Op::Inc(UnOp::new(Operand::Glb(Memory::HEAP_BASE)), OpType::U32),
+            Op::End(Operand::Glb(Memory::HEAP_BASE)),
+        ],
+    }];
+
+    let mut exe = Executor::from_limits(&functions, 16, 8);
+    exe.call(0, 0).unwrap();
+
+    let fault = MemoryError::SegmentationFault(0, 4);
+    assert_eq!(exe.execute(), Err(ExecutionError::MemoryError(fault)));
+
+    assert_eq!(exe.grow_heap(4), Ok(Memory::HEAP_BASE));
+    assert_eq!(exe.grow_heap(4), Ok(Memory::HEAP_BASE + 4));
+    assert_eq!(exe.memory().heap.len(), 8);
+    assert_eq!(
+        exe.grow_heap(1),
+        Err(ExecutionError::MemoryError(MemoryError::PageOverflow(
+            "heap"
+        )))
+    );
+
+    assert_eq!(exe.run(), Ok(ExecutionSuccess::End(1)));
+}
+
+#[test]
+fn executor_run_async() {
+    use std::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    };
+
+    let functions = [Function {
+        frame_size: 8,
+        program: &[
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::Uw),
+            Op::Ifl(BinOp::new(Operand::Loc(0), Operand::Val(3)), OpType::Uw),
+            Op::Jmp(Operand::Val(-2 as IWord as UWord)),
+            Op::Slp(Operand::Val(10)),
+            Op::End(Operand::Loc(0)),
+        ],
+    }];
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut polls = 0;
+    let mut poll = |exe: &mut Executor| loop {
+        polls += 1;
+        if let Poll::Ready(res) = Pin::new(&mut exe.run_async(2)).poll(&mut cx) {
+            break res;
+        }
+    };
+
+    assert_eq!(poll(&mut exe), Ok(ExecutionSuccess::Sleep(10)));
+    assert_eq!(poll(&mut exe), Ok(ExecutionSuccess::End(3)));
+    assert_eq!(polls, 6);
+}
+
+#[test]
+fn executor_trap_location() {
+    use crate::executor::debugger::Location;
+
+    let functions = [Function {
+        frame_size: 4,
+        program: &[
+            Op::Set(BinOp::new(Operand::Loc(0), Operand::Val(7)), OpType::U32),
+            Op::Div(BinOp::new(Operand::Loc(0), Operand::Val(0)), OpType::U32),
+        ],
+    }];
+
+    let mut exe = Executor::new(&functions);
+    assert_eq!(exe.location(), None);
+    exe.call(0, 0).unwrap();
+
+    let err = exe.run().unwrap_err();
+    assert_eq!(err, ExecutionError::DivisionByZero);
+    assert_eq!(exe.location(), Some(Location { function: 0, op: 1 }));
+
+    let err = std::io::Error::from(err);
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    assert_eq!(err.to_string(), "division by zero");
+
+    let err = ExecutionError::MemoryError(MemoryError::SegmentationFault(16, 4));
+    assert_eq!(err.to_string(), "segmentation fault: 4 bytes at 0x10");
+}
+
+#[test]
+fn executor_debugger() {
+    use crate::executor::debugger::{Debugger, Location};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        steps: Rc<RefCell<Vec<UWord>>>,
+        traps: Rc<RefCell<Vec<(Location, ExecutionError)>>>,
+    }
+
+    impl Debugger for Recorder {
+        fn on_step(&mut self, location: Location, _: &Op) {
+            self.steps.borrow_mut().push(location.op);
+        }
+
+        fn on_trap(&mut self, location: Location, error: ExecutionError) {
+            self.traps.borrow_mut().push((location, error));
+        }
+
+        fn is_breakpoint(&self, location: Location) -> bool {
+            location.op == 2
+        }
+    }
+
+    let functions = [Function {
+        frame_size: 1,
+        program: &[
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::U8),
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::U8),
+            Op::Div(BinOp::new(Operand::Loc(0), Operand::Val(0)), OpType::U8),
+        ],
+    }];
+
+    let recorder = Recorder::default();
+    let steps = Rc::clone(&recorder.steps);
+    let traps = Rc::clone(&recorder.traps);
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+    exe.attach(recorder);
+
+    assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::Break));
+    assert_eq!(*steps.borrow(), [0, 1]);
+    assert_eq!(exe.get_val::<u8>(Operand::Loc(0)), Ok(2));
+
+    assert_eq!(exe.run(), Executed::Err(ExecutionError::DivisionByZero));
+    assert_eq!(*steps.borrow(), [0, 1, 2]);
+    assert_eq!(
+        *traps.borrow(),
+        [(
+            Location { function: 0, op: 2 },
+            ExecutionError::DivisionByZero
+        )]
+    );
+
+    assert!(exe.detach().is_some());
+}
+
+#[test]
+fn executor_float_ops() {
+    fn exec(op: Op, x: f32, y: f32) -> Result<f32, ExecutionError> {
+        let functions = [Function {
+            frame_size: 8,
+            program: &[op, Op::End(Operand::Val(0))],
+        }];
+
+        let mut exe = Executor::new(&functions);
+        exe.call(0, 0).unwrap();
+        exe.set_val(Operand::Loc(0), x).unwrap();
+        exe.set_val(Operand::Loc(4), y).unwrap();
+        exe.run()?;
+        exe.get_val(Operand::Loc(0))
+    }
+
+    let xy = BinOp::new(Operand::Loc(0), Operand::Loc(4));
+    let sqt = Op::Sqt(UnOp::new(Operand::Loc(0)), OpType::F32);
+    let abs = Op::Abs(UnOp::new(Operand::Loc(0)), OpType::F32);
+    let min = Op::Min(xy, OpType::F32);
+    let max = Op::Max(xy, OpType::F32);
+
+    assert_eq!(exec(sqt, 2., 0.), Ok(std::f32::consts::SQRT_2));
+    assert_eq!(exec(sqt, f32::INFINITY, 0.), Ok(f32::INFINITY));
+    assert!(exec(sqt, -1., 0.).unwrap().is_nan());
+    assert!(exec(sqt, -0., 0.).unwrap().is_sign_negative());
+
+    assert_eq!(exec(abs, -3., 0.), Ok(3.));
+    assert_eq!(exec(abs, f32::NEG_INFINITY, 0.), Ok(f32::INFINITY));
+    assert!(exec(abs, -0., 0.).unwrap().is_sign_positive());
+
+    assert_eq!(exec(min, 1., -2.), Ok(-2.));
+    assert_eq!(exec(max, 1., -2.), Ok(1.));
+    assert_eq!(exec(min, f32::NEG_INFINITY, 0.), Ok(f32::NEG_INFINITY));
+    assert!(exec(min, 0., -0.).unwrap().is_sign_negative());
+    assert!(exec(max, -0., 0.).unwrap().is_sign_positive());
+    assert!(exec(min, f32::NAN, 1.).unwrap().is_nan());
+    assert!(exec(max, 1., f32::NAN).unwrap().is_nan());
+}
+
+#[test]
+fn executor_int_abs_min_max() {
+    let functions = [Function {
+        frame_size: 2,
+        program: &[
+            Op::Set(
+                BinOp::new(Operand::Loc(0), Operand::Val(-128 as IWord as UWord)),
+                OpType::I8,
+            ),
+            Op::Abs(UnOp::new(Operand::Loc(0)), OpType::I8),
+            Op::Set(
+                BinOp::new(Operand::Loc(1), Operand::Val(-5 as IWord as UWord)),
+                OpType::I8,
+            ),
+            Op::Max(BinOp::new(Operand::Loc(1), Operand::Val(3)), OpType::I8),
+            Op::Sqt(UnOp::new(Operand::Loc(0)), OpType::U8),
+        ],
+    }];
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+
+    assert_eq!(
+        exe.run(),
+        Executed::Err(ExecutionError::IncorrectOperation(functions[0].program[4]))
+    );
+    assert_eq!(exe.get_val::<i8>(Operand::Loc(0)), Ok(-128));
+    assert_eq!(exe.get_val::<i8>(Operand::Loc(1)), Ok(3));
+}
+
+#[test]
+fn executor_snapshot() {
+    let functions = [Function {
+        frame_size: 8,
+        program: &[
+            Op::Set(BinOp::new(Operand::Loc(0), Operand::Val(2)), OpType::U32),
+            Op::Cnv(Operand::Loc(0), Operand::Loc(0), OpType::U32, OpType::F32),
+            Op::Slp(Operand::Val(0)),
+            Op::Sqt(UnOp::new(Operand::Loc(0)), OpType::F32),
+            Op::Mul(BinOp::new(Operand::Loc(0), Operand::Loc(0)), OpType::F32),
+            Op::End(Operand::Loc(0)),
+        ],
+    }];
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+    exe.set_fuel(Some(10));
+
+    assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::Sleep(0)));
+    let snapshot = exe.snapshot();
+
+    // The result of sqrt(2)^2 is rounded to nearest, so it's just below 2
+    let expected = 0x3FFF_FFFF;
+
+    assert_eq!(
+        exe.run(),
+        Executed::Ok(ExecutionSuccess::End(expected as UWord))
+    );
+    assert_eq!(exe.fuel(), Some(4));
+
+    exe.restore(&snapshot);
+    assert_eq!(exe.fuel(), Some(7));
+    assert_eq!(exe.get_val::<f32>(Operand::Loc(0)), Ok(2.));
+    assert_eq!(
+        exe.run(),
+        Executed::Ok(ExecutionSuccess::End(expected as UWord))
+    );
+}
+
+#[test]
+fn executor_watchpoints() {
+    use crate::executor::debugger::{Debugger, Location, Watch};
+    use std::{cell::RefCell, rc::Rc};
+
+    type Hit = (Location, Watch, Vec<u8>, Vec<u8>);
+
+    #[derive(Debug)]
+    struct Watcher {
+        watches: Vec<Watch>,
+        hits: Rc<RefCell<Vec<Hit>>>,
+    }
+
+    impl Debugger for Watcher {
+        fn watches(&self) -> &[Watch] {
+            &self.watches
+        }
+
+        fn on_watch(&mut self, location: Location, watch: &Watch, old: &[u8], new: &[u8]) {
+            self.hits
+                .borrow_mut()
+                .push((location, watch.clone(), old.to_vec(), new.to_vec()));
+        }
+    }
+
+    let functions = [Function {
+        frame_size: 2,
+        program: &[
+            Op::Set(BinOp::new(Operand::Loc(1), Operand::Val(5)), OpType::U8),
+            Op::Set(BinOp::new(Operand::Loc(0), Operand::Val(7)), OpType::U8),
+            Op::Set(BinOp::new(Operand::Loc(0), Operand::Val(7)), OpType::U8),
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::U8),
+        ],
+    }];
+
+    let hits = Rc::new(RefCell::new(Vec::new()));
+    let watcher = Watcher {
+        watches: vec![Watch::Local(0..1), Watch::Memory(1..2)],
+        hits: Rc::clone(&hits),
+    };
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+    exe.attach(watcher);
+
+    let at = |op| Location { function: 0, op };
+
+    assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::Break));
+    assert_eq!(
+        *hits.borrow(),
+        [(at(0), Watch::Memory(1..2), vec![0], vec![5])]
+    );
+
+    // Writing the same value doesn't trigger the watchpoint
+    assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::Break));
+    assert_eq!(exe.run(), Executed::Ok(ExecutionSuccess::Break));
+    assert_eq!(
+        hits.borrow()[1..],
+        [
+            (at(1), Watch::Local(0..1), vec![0], vec![7]),
+            (at(3), Watch::Local(0..1), vec![7], vec![8]),
+        ]
+    );
+    assert_eq!(exe.get_val::<u8>(Operand::Loc(0)), Ok(8));
+}
+
+#[test]
+fn executor_debugger_out_of_fuel() {
+    use crate::executor::debugger::{Debugger, Location};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        events: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl Debugger for Recorder {
+        fn on_step(&mut self, _: Location, _: &Op) {
+            self.events.borrow_mut().push("step");
+        }
+
+        fn on_trap(&mut self, _: Location, _: ExecutionError) {
+            self.events.borrow_mut().push("trap");
+        }
+    }
+
+    let functions = [Function {
+        frame_size: 1,
+        program: &[
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::U8),
+            Op::Inc(UnOp::new(Operand::Loc(0)), OpType::U8),
+        ],
+    }];
+
+    let recorder = Recorder::default();
+    let events = Rc::clone(&recorder.events);
+
+    let mut exe = Executor::new(&functions);
+    exe.call(0, 0).unwrap();
+    exe.set_fuel(Some(1));
+    exe.attach(recorder);
+
+    assert_eq!(exe.run(), Executed::Err(ExecutionError::OutOfFuel));
+    assert_eq!(*events.borrow(), ["step"]);
+    assert_eq!(exe.location(), Some(Location { function: 0, op: 1 }));
+}
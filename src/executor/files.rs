@@ -8,6 +8,17 @@ pub enum FileError {
     WritingNotAvailable,
 }
 
+impl std::fmt::Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileError::ReadingNotAvailable => write!(f, "reading is not available"),
+            FileError::WritingNotAvailable => write!(f, "writing is not available"),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
 pub trait File: std::fmt::Debug {
     fn read(&mut self) -> Result<Option<u8>, FileError>;
 
@@ -59,13 +70,28 @@ pub enum FilesError {
     NotFound,
 }
 
+impl std::fmt::Display for FilesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use FilesError::*;
+
+        match self {
+            FileError(e) => e.fmt(f),
+            CurrentIsNotSet => write!(f, "current file is not set"),
+            LimitExceeded => write!(f, "file limit exceeded"),
+            NotFound => write!(f, "file not found"),
+        }
+    }
+}
+
+impl std::error::Error for FilesError {}
+
 impl From<FileError> for FilesError {
     fn from(e: FileError) -> Self {
         FilesError::FileError(e)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Files {
     files: Vec<Option<Box<dyn File>>>,
     count: usize,
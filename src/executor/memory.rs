@@ -9,6 +9,24 @@ pub enum MemoryError {
     WrongRange,
 }
 
+impl std::fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use MemoryError::*;
+
+        match self {
+            PageOverflow(page) => write!(f, "{} overflow", page),
+            RageUnderflow(page) => write!(f, "{} underflow", page),
+            SegmentationFault(ptr, size) => {
+                write!(f, "segmentation fault: {} bytes at {:#x}", size, ptr)
+            }
+            WrongRange => write!(f, "wrong range"),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
+
+#[derive(Clone)]
 pub struct MemoryPage {
     page: Vec<u8>,
     limit: usize,
@@ -51,6 +69,15 @@ impl MemoryPage {
         self.page.len() as UWord
     }
 
+    /// Maximum length of the page.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.page.is_empty()
+    }
+
     pub fn get(&self, ptr: UWord, size: UWord) -> Result<&[u8], MemoryError> {
         self.page
             .get(ptr as usize..ptr.wrapping_add(size) as usize)
@@ -92,18 +119,16 @@ impl std::fmt::Debug for MemoryPage {
         }
 
         let mut counter = 0;
-        let mut line = 0;
 
         f.write_char('\n')?;
 
-        for &byte in self.page.iter() {
+        for (line, &byte) in self.page.iter().enumerate() {
             if counter == 0 {
                 write!(f, "{:02X?}:  ", line)?;
             }
 
             write!(f, "{:02X?} ", byte)?;
             counter += 1;
-            line += 1;
 
             if counter > 8 {
                 f.write_char('\n')?;
@@ -115,7 +140,13 @@ impl std::fmt::Debug for MemoryPage {
     }
 }
 
-#[derive(Debug)]
+/// Memory of the executor.
+///
+/// Addresses below `HEAP_BASE` are on the stack, which holds function frames.
+/// The heap starts at `HEAP_BASE` and is empty until the host grows it.
+/// Both pages only grow up to their limits, new bytes are always zeroed,
+/// and any access outside of the current length is a `SegmentationFault`.
+#[derive(Clone, Debug)]
 pub struct Memory {
     pub stack: MemoryPage,
     pub heap: MemoryPage,
@@ -172,7 +203,7 @@ impl Memory {
 
         // If dest and src are on the left or on the right side together then
         // they are in the same memory page.
-        return if dest_on_stack == src_on_stack {
+        if dest_on_stack == src_on_stack {
             // And then it allows to make a memmove.
             if dest_on_stack {
                 self.stack.memmove(dest, src, size)
@@ -193,7 +224,7 @@ impl Memory {
 
             dest_slice.copy_from_slice(src_slice);
             Ok(())
-        };
+        }
     }
 
     pub fn set_zeros(&mut self, dest: UWord, size: UWord) -> Result<(), MemoryError> {
@@ -209,6 +240,11 @@ impl Memory {
         Ok(a_slice == b_slice)
     }
 
+    /// Returns `size` bytes starting at `ptr`.
+    pub fn bytes(&self, ptr: UWord, size: UWord) -> Result<&[u8], MemoryError> {
+        self.slice(ptr, size)
+    }
+
     fn slice(&self, ptr: UWord, size: UWord) -> Result<&[u8], MemoryError> {
         if ptr < Memory::HEAP_BASE {
             self.stack.get(ptr, size)
@@ -246,6 +282,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn memory_zeroed() {
+        let mut mem = Memory::from_limits(2048, 2048);
+        mem.stack.expand(4).unwrap();
+        mem.set(0, u32::MAX).unwrap();
+        mem.stack.narrow(4).unwrap();
+        mem.stack.expand(4).unwrap();
+        assert_eq!(mem.get::<u32>(0), Ok(0));
+        assert_eq!(mem.get::<u32>(1), Err(MemoryError::SegmentationFault(1, 4)));
+    }
+
     #[test]
     fn memory_set_get_stack() {
         let mut mem = Memory::from_limits(2048, 2048);
@@ -1,6 +1,9 @@
+pub mod coverage;
+pub mod debugger;
+#[allow(clippy::module_inception)]
 mod executor;
-mod files;
-mod memory;
+pub mod files;
+pub mod memory;
 pub mod primary;
 
 pub use executor::*;
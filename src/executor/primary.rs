@@ -369,6 +369,117 @@ where
     }
 }
 
+pub trait Sqrt: Primary {
+    fn sqrt(self) -> Self;
+}
+
+impl Sqrt for f32 {
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+}
+
+impl Sqrt for f64 {
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+}
+
+pub trait Abs: Primary {
+    fn wrapping(self) -> Self;
+}
+
+macro_rules! impl_abs_unsigned {
+    ($($t:ty),+) => {
+        $(
+        impl Abs for $t {
+            fn wrapping(self) -> Self { self }
+        }
+        )+
+    }
+}
+
+impl_abs_unsigned!(u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_abs {
+    ($($t:ty),+) => {
+        $(
+        impl Abs for $t {
+            fn wrapping(self) -> Self { self.wrapping_abs() }
+        }
+        )+
+    }
+}
+
+impl_abs!(i8, i16, i32, i64, i128, isize);
+
+impl Abs for f32 {
+    fn wrapping(self) -> Self {
+        self.abs()
+    }
+}
+
+impl Abs for f64 {
+    fn wrapping(self) -> Self {
+        self.abs()
+    }
+}
+
+/// Minimum and maximum.
+///
+/// For floats, NaN is returned if any operand is NaN, and `-0` is less than `+0`.
+pub trait MinMax: Primary {
+    fn minimum(self, r: Self) -> Self;
+    fn maximum(self, r: Self) -> Self;
+}
+
+macro_rules! impl_min_max {
+    ($($t:ty),+) => {
+        $(
+        impl MinMax for $t {
+            fn minimum(self, r: Self) -> Self { Ord::min(self, r) }
+            fn maximum(self, r: Self) -> Self { Ord::max(self, r) }
+        }
+        )+
+    }
+}
+
+impl_min_max!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize);
+
+macro_rules! impl_min_max_f {
+    ($($t:ty),+) => {
+        $(
+        impl MinMax for $t {
+            fn minimum(self, r: Self) -> Self {
+                if self.is_nan() || r.is_nan() {
+                    <$t>::NAN
+                } else if self == r {
+                    if self.is_sign_negative() { self } else { r }
+                } else if self < r {
+                    self
+                } else {
+                    r
+                }
+            }
+
+            fn maximum(self, r: Self) -> Self {
+                if self.is_nan() || r.is_nan() {
+                    <$t>::NAN
+                } else if self == r {
+                    if self.is_sign_positive() { self } else { r }
+                } else if self > r {
+                    self
+                } else {
+                    r
+                }
+            }
+        }
+        )+
+    }
+}
+
+impl_min_max_f!(f32, f64);
+
 pub trait Convert<T>: Primary {
     fn convert(v: T) -> Self;
 }
@@ -1,8 +1,13 @@
 #[macro_use]
 extern crate pest_derive;
 
+pub mod analysis;
+pub mod assembler;
+pub mod builder;
 pub mod common;
 pub mod decoder;
 pub mod encoder;
 pub mod executor;
+pub mod module;
 pub mod nil;
+pub mod validator;
@@ -0,0 +1,202 @@
+use super::{Module, ModuleFunction};
+use crate::common::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LinkError {
+    /// Two modules define a function with the same name.
+    DuplicateExport(String),
+    /// No module defines an imported function.
+    UnresolvedImport(String),
+    /// A call with a computed function id, which can't be rewritten.
+    ComputedCall { module: usize, function: usize },
+    /// A call to a function id the module doesn't have.
+    UnknownFunction { module: usize, id: UWord },
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use LinkError::*;
+
+        match self {
+            DuplicateExport(name) => write!(f, "duplicate export {}", name),
+            UnresolvedImport(name) => write!(f, "unresolved import {}", name),
+            ComputedCall { module, function } => write!(
+                f,
+                "computed call in function {} of module {}",
+                function, module
+            ),
+            UnknownFunction { module, id } => {
+                write!(f, "unknown function {} in module {}", id, module)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+/// Merges modules into one.
+///
+/// Function definitions are placed in order of the modules. Imports are resolved
+/// by name to a definition from any module and `app` operations are rewritten to
+/// the new function ids.
+pub fn link(modules: &[Module]) -> Result<Module, LinkError> {
+    let mut exports = HashMap::new();
+    let mut ids = vec![];
+    let mut next = 0;
+
+    for module in modules {
+        let mut module_ids = vec![];
+        for f in module.functions() {
+            if f.is_import() {
+                module_ids.push(None);
+                continue;
+            }
+
+            if let Some(name) = &f.name {
+                if exports.insert(name.as_str(), next).is_some() {
+                    return Err(LinkError::DuplicateExport(name.clone()));
+                }
+            }
+
+            module_ids.push(Some(next));
+            next += 1;
+        }
+
+        ids.push(module_ids);
+    }
+
+    let mut linked = Module::new();
+    for (m, module) in modules.iter().enumerate() {
+        let map = module
+            .functions()
+            .iter()
+            .zip(&ids[m])
+            .map(|(f, id)| match (id, &f.name) {
+                (Some(id), _) => Ok(*id),
+                (None, Some(name)) => exports
+                    .get(name.as_str())
+                    .copied()
+                    .ok_or_else(|| LinkError::UnresolvedImport(name.clone())),
+                (None, None) => unreachable!(),
+            })
+            .collect::<Result<Vec<UWord>, _>>()?;
+
+        for (function, f) in module.functions().iter().enumerate() {
+            if f.is_import() {
+                continue;
+            }
+
+            let program = f
+                .program
+                .iter()
+                .map(|op| match op {
+                    Op::App(Operand::Val(id)) => map
+                        .get(*id as usize)
+                        .map(|&id| Op::App(Operand::Val(id)))
+                        .ok_or(LinkError::UnknownFunction { module: m, id: *id }),
+                    Op::App(_) => Err(LinkError::ComputedCall {
+                        module: m,
+                        function,
+                    }),
+                    op => Ok(*op),
+                })
+                .collect::<Result<_, _>>()?;
+
+            linked.push(ModuleFunction {
+                program,
+                ..f.clone()
+            });
+        }
+    }
+
+    Ok(linked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assembler::assemble,
+        executor::{ExecutionSuccess, Executor},
+    };
+
+    #[test]
+    fn link_modules() {
+        let mut lib = Module::new();
+        lib.push(ModuleFunction::new(1, assemble("ret u8 emp").unwrap()));
+        lib.push(
+            ModuleFunction::new(
+                4,
+                assemble("add u32 loc(0) 1\nset u32 ^0 loc(0)\nret u8 emp").unwrap(),
+            )
+            .with_name("inc"),
+        );
+
+        let main = assemble(
+            r#"
+            set u32 loc(0) 41
+            app 1
+            par u32 loc(0)
+            clf ref(0)
+            end loc(0)
+            "#,
+        )
+        .unwrap();
+
+        let mut app = Module::new();
+        app.push(ModuleFunction::new(8, main).with_name("main"));
+        app.push(ModuleFunction::import("inc"));
+
+        let linked = link(&[app, lib]).unwrap();
+        assert_eq!(linked.functions().len(), 3);
+        assert_eq!(linked.functions()[0].program[1], Op::App(Operand::Val(2)));
+
+        let functions = linked.executor_functions();
+        let mut exe = Executor::new(&functions);
+        exe.call(0, 0).unwrap();
+        assert_eq!(exe.run(), Ok(ExecutionSuccess::End(42)));
+    }
+
+    #[test]
+    fn link_errors() {
+        let f = || ModuleFunction::new(1, vec![Op::Ret(UnOp::new(Operand::Emp), OpType::U8)]);
+
+        let mut a = Module::new();
+        a.push(f().with_name("f"));
+        let mut b = Module::new();
+        b.push(f().with_name("f"));
+        assert_eq!(
+            link(&[a.clone(), b]),
+            Err(LinkError::DuplicateExport("f".into()))
+        );
+
+        let mut c = Module::new();
+        c.push(ModuleFunction::import("g"));
+        assert_eq!(
+            link(&[a.clone(), c]),
+            Err(LinkError::UnresolvedImport("g".into()))
+        );
+
+        let mut d = Module::new();
+        d.push(ModuleFunction::new(4, vec![Op::App(Operand::Loc(0))]));
+        assert_eq!(
+            link(&[a.clone(), d]),
+            Err(LinkError::ComputedCall {
+                module: 1,
+                function: 0
+            })
+        );
+
+        let mut e = Module::new();
+        e.push(ModuleFunction::new(1, vec![Op::App(Operand::Val(3))]));
+        assert_eq!(
+            link(&[a, e]),
+            Err(LinkError::UnknownFunction { module: 1, id: 3 })
+        );
+        assert_eq!(
+            LinkError::UnresolvedImport("g".into()).to_string(),
+            "unresolved import g"
+        );
+    }
+}
@@ -0,0 +1,6 @@
+mod link;
+#[allow(clippy::module_inception)]
+mod module;
+
+pub use link::*;
+pub use module::*;
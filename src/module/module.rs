@@ -0,0 +1,593 @@
+use crate::{
+    common::*,
+    decoder::{decode::*, DecodeError},
+    encoder::{encode::*, EncodeError},
+    executor::Function,
+};
+use std::io::{self, Read, Write};
+
+pub const MAGIC: [u8; 4] = *b"nib\0";
+pub const VERSION: u16 = 1;
+
+const WORD_SIZE: usize = std::mem::size_of::<UWord>();
+
+/// Section identifiers.
+///
+/// A section is written as its id, a `u32` payload length and the payload.
+/// Unknown sections are skipped on load.
+pub mod sections {
+    /// Encoded operations of all functions.
+    pub const CODE: u8 = 1;
+    /// Frame size, code offset and code length of each function.
+    pub const FUNCTIONS: u8 = 2;
+    /// Optional function names.
+    pub const NAMES: u8 = 3;
+}
+
+#[derive(Debug)]
+pub enum ModuleError {
+    ReadError(io::Error),
+    UnexpectedEnd,
+    IncorrectMagic,
+    UnsupportedVersion(u16),
+    /// The program needs a newer operation table than the runtime has.
+    UnsupportedIsa {
+        required: u16,
+        supported: u16,
+    },
+    /// The program needs capabilities the runtime doesn't have.
+    MissingCapabilities(Capabilities),
+    /// The checksum in the header doesn't match the sections.
+    IntegrityError {
+        expected: u32,
+        actual: u32,
+    },
+    DuplicateSection(u8),
+    MissingSection(u8),
+    /// The function's frame size or code range doesn't fit the module.
+    IncorrectFunction(usize),
+    /// The names section doesn't match the function table or isn't UTF-8.
+    IncorrectNames,
+    DecodeError(DecodeError),
+}
+
+impl From<io::Error> for ModuleError {
+    fn from(e: io::Error) -> Self {
+        ModuleError::ReadError(e)
+    }
+}
+
+impl From<DecodeError> for ModuleError {
+    fn from(e: DecodeError) -> Self {
+        ModuleError::DecodeError(e)
+    }
+}
+
+impl std::fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ModuleError::*;
+
+        match self {
+            ReadError(e) => write!(f, "read error: {}", e),
+            UnexpectedEnd => write!(f, "unexpected end of module"),
+            IncorrectMagic => write!(f, "not a module"),
+            UnsupportedVersion(v) => write!(f, "unsupported module version {}", v),
+            UnsupportedIsa {
+                required,
+                supported,
+            } => write!(
+                f,
+                "the program requires instruction set version {}, but only {} is supported",
+                required, supported
+            ),
+            MissingCapabilities(caps) => write!(f, "missing capabilities: {}", caps),
+            IntegrityError { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected {:#010x}, found {:#010x}",
+                expected, actual
+            ),
+            DuplicateSection(id) => write!(f, "duplicate section {}", id),
+            MissingSection(id) => write!(f, "missing section {}", id),
+            IncorrectFunction(idx) => write!(f, "incorrect function {}", idx),
+            IncorrectNames => write!(f, "incorrect names section"),
+            DecodeError(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ModuleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ModuleError::ReadError(e) => Some(e),
+            ModuleError::DecodeError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModuleFunction {
+    pub name: Option<String>,
+    pub frame_size: UWord,
+    pub program: Vec<Op>,
+}
+
+impl ModuleFunction {
+    pub fn new(frame_size: UWord, program: Vec<Op>) -> Self {
+        Self {
+            name: None,
+            frame_size,
+            program,
+        }
+    }
+
+    pub fn with_name<S>(mut self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Declares a function defined in another module. It is resolved by name when linking.
+    pub fn import<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(0, Vec::new()).with_name(name)
+    }
+
+    pub fn is_import(&self) -> bool {
+        self.program.is_empty() && self.name.is_some()
+    }
+}
+
+/// A program saved as a single blob: a header followed by sections.
+///
+/// The header is the magic `nib\0`, a `u16` version, `u16` flags, which are reserved,
+/// the `u16` instruction set version and `u32` capabilities the program requires,
+/// and the CRC-32 of everything after the header. All integers are little-endian.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Module {
+    functions: Vec<ModuleFunction>,
+}
+
+impl Module {
+    pub fn new() -> Self {
+        Self {
+            functions: Vec::new(),
+        }
+    }
+
+    /// Adds a function and returns its id.
+    pub fn push(&mut self, function: ModuleFunction) -> UWord {
+        self.functions.push(function);
+        (self.functions.len() - 1) as UWord
+    }
+
+    pub fn functions(&self) -> &[ModuleFunction] {
+        &self.functions
+    }
+
+    /// The operation table version the program requires.
+    pub fn isa_version(&self) -> u16 {
+        self.ops().map(Op::isa_version).max().unwrap_or(1)
+    }
+
+    /// Capabilities the program requires.
+    pub fn capabilities(&self) -> Capabilities {
+        self.ops()
+            .map(Op::capabilities)
+            .fold(Capabilities::empty(), Capabilities::union)
+    }
+
+    fn ops(&self) -> impl Iterator<Item = &Op> {
+        self.functions.iter().flat_map(|f| &f.program)
+    }
+
+    /// Makes an executor function table.
+    pub fn executor_functions(&self) -> Vec<Function<'_>> {
+        self.functions
+            .iter()
+            .map(|f| Function::new(f.frame_size, &f.program))
+            .collect()
+    }
+}
+
+/// Whether to verify the module checksum on load.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Checksum {
+    Verify,
+    Skip,
+}
+
+/// CRC-32 (IEEE 802.3), the same as used by zip and png.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+fn write_section<W>(buf: &mut W, id: u8, payload: &[u8]) -> Result<(), EncodeError>
+where
+    W: Write,
+{
+    id.encode(buf)?;
+    write_bytes(buf, &(payload.len() as u32).to_le_bytes())?;
+    write_bytes(buf, payload)
+}
+
+fn write_bytes<W>(buf: &mut W, bytes: &[u8]) -> Result<(), EncodeError>
+where
+    W: Write,
+{
+    buf.write(bytes).expected(bytes.len())
+}
+
+impl Encode for Module {
+    type Err = EncodeError;
+
+    fn encode<W>(&self, buf: &mut W) -> Result<(), Self::Err>
+    where
+        W: Write,
+    {
+        let mut code = vec![];
+        let mut table = vec![];
+        table.extend(&(self.functions.len() as u32).to_le_bytes());
+
+        for f in &self.functions {
+            let start = code.len();
+            for op in &f.program {
+                op.encode(&mut code)?;
+            }
+
+            let mut frame_size = [0; 8];
+            frame_size[..WORD_SIZE].copy_from_slice(&f.frame_size.to_le_bytes());
+            table.extend(&frame_size);
+            table.extend(&(start as u32).to_le_bytes());
+            table.extend(&((code.len() - start) as u32).to_le_bytes());
+        }
+
+        let mut body = vec![];
+        write_section(&mut body, sections::CODE, &code)?;
+        write_section(&mut body, sections::FUNCTIONS, &table)?;
+
+        if self.functions.iter().any(|f| f.name.is_some()) {
+            let mut names = vec![];
+            for f in &self.functions {
+                let name = f.name.as_deref().unwrap_or_default();
+                names.extend(&(name.len() as u32).to_le_bytes());
+                names.extend(name.as_bytes());
+            }
+
+            write_section(&mut body, sections::NAMES, &names)?;
+        }
+
+        write_bytes(buf, &MAGIC)?;
+        write_bytes(buf, &VERSION.to_le_bytes())?;
+        write_bytes(buf, &0_u16.to_le_bytes())?;
+        write_bytes(buf, &self.isa_version().to_le_bytes())?;
+        write_bytes(buf, &self.capabilities().bits().to_le_bytes())?;
+        write_bytes(buf, &crc32(&body).to_le_bytes())?;
+        write_bytes(buf, &body)
+    }
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+}
+
+impl<'b> Reader<'b> {
+    fn take(&mut self, n: usize) -> Result<&'b [u8], ModuleError> {
+        if self.bytes.len() < n {
+            return Err(ModuleError::UnexpectedEnd);
+        }
+
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, ModuleError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ModuleError> {
+        let mut buf = [0; 2];
+        buf.copy_from_slice(self.take(2)?);
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    fn u32(&mut self) -> Result<u32, ModuleError> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    /// Reads a 64-bit value that must fit in a word.
+    fn word(&mut self) -> Result<Option<UWord>, ModuleError> {
+        let (low, high) = self.take(8)?.split_at(WORD_SIZE);
+        if high.iter().any(|&b| b != 0) {
+            return Ok(None);
+        }
+
+        let mut buf = [0; WORD_SIZE];
+        buf.copy_from_slice(low);
+        Ok(Some(UWord::from_le_bytes(buf)))
+    }
+}
+
+fn decode_code(mut code: &[u8]) -> Result<Vec<Op>, DecodeError> {
+    let mut program = vec![];
+    while !code.is_empty() {
+        program.push(decode(&mut code)?);
+    }
+
+    Ok(program)
+}
+
+impl Decode<()> for Module {
+    type Err = ModuleError;
+
+    fn decode<R>(bytes: &mut R, _: ()) -> Result<Self, Self::Err>
+    where
+        R: Read,
+    {
+        decode_with(bytes, Checksum::Verify)
+    }
+}
+
+impl Decode<Checksum> for Module {
+    type Err = ModuleError;
+
+    fn decode<R>(bytes: &mut R, checksum: Checksum) -> Result<Self, Self::Err>
+    where
+        R: Read,
+    {
+        let mut buf = vec![];
+        bytes.read_to_end(&mut buf)?;
+        let mut reader = Reader { bytes: &buf };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ModuleError::IncorrectMagic);
+        }
+
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(ModuleError::UnsupportedVersion(version));
+        }
+
+        let _flags = reader.u16()?;
+
+        let required = reader.u16()?;
+        if required > op_codes::ISA_VERSION {
+            return Err(ModuleError::UnsupportedIsa {
+                required,
+                supported: op_codes::ISA_VERSION,
+            });
+        }
+
+        let missing = Capabilities::from_bits(reader.u32()?).difference(Capabilities::SUPPORTED);
+        if !missing.is_empty() {
+            return Err(ModuleError::MissingCapabilities(missing));
+        }
+
+        let expected = reader.u32()?;
+        if checksum == Checksum::Verify {
+            let actual = crc32(reader.bytes);
+            if actual != expected {
+                return Err(ModuleError::IntegrityError { expected, actual });
+            }
+        }
+
+        let mut code = None;
+        let mut table = None;
+        let mut names = None;
+
+        while !reader.bytes.is_empty() {
+            let id = reader.u8()?;
+            let len = reader.u32()? as usize;
+            let payload = reader.take(len)?;
+
+            let section = match id {
+                sections::CODE => &mut code,
+                sections::FUNCTIONS => &mut table,
+                sections::NAMES => &mut names,
+                _ => continue,
+            };
+
+            if section.replace(payload).is_some() {
+                return Err(ModuleError::DuplicateSection(id));
+            }
+        }
+
+        let code = code.ok_or(ModuleError::MissingSection(sections::CODE))?;
+        let mut table = Reader {
+            bytes: table.ok_or(ModuleError::MissingSection(sections::FUNCTIONS))?,
+        };
+
+        let mut module = Module::new();
+        for idx in 0..table.u32()? as usize {
+            let frame_size = table.word()?;
+            let start = table.u32()? as usize;
+            let len = table.u32()? as usize;
+
+            let frame_size = frame_size.ok_or(ModuleError::IncorrectFunction(idx))?;
+
+            let fn_code = start
+                .checked_add(len)
+                .and_then(|end| code.get(start..end))
+                .ok_or(ModuleError::IncorrectFunction(idx))?;
+
+            module.push(ModuleFunction::new(frame_size, decode_code(fn_code)?));
+        }
+
+        if let Some(names) = names {
+            let mut names = Reader { bytes: names };
+            for f in &mut module.functions {
+                let len = names.u32().map_err(|_| ModuleError::IncorrectNames)? as usize;
+                let name = names.take(len).map_err(|_| ModuleError::IncorrectNames)?;
+                let name = std::str::from_utf8(name).map_err(|_| ModuleError::IncorrectNames)?;
+
+                if !name.is_empty() {
+                    f.name = Some(name.to_string());
+                }
+            }
+
+            if !names.bytes.is_empty() {
+                return Err(ModuleError::IncorrectNames);
+            }
+        }
+
+        Ok(module)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assembler::assemble,
+        executor::{ExecutionSuccess, Executor},
+    };
+
+    fn sample() -> Module {
+        let main = assemble(
+            r#"
+            set uw loc(0) 4
+            app 1
+            par u32 loc(0)
+            clf ref(0)
+            end loc(0)
+            "#,
+        )
+        .unwrap();
+
+        let double = assemble(
+            r#"
+            add u32 loc(0) loc(0)
+            set u32 ret(0) loc(0)
+            ret u8 emp
+            "#,
+        )
+        .unwrap();
+
+        let mut module = Module::new();
+        module.push(ModuleFunction::new(8, main).with_name("main"));
+        module.push(ModuleFunction::new(4, double));
+        module
+    }
+
+    fn with_header(body: &[u8]) -> Vec<u8> {
+        let mut blob = b"nib\0\x01\0\0\0\x01\0\0\0\0\0".to_vec();
+        blob.extend(&crc32(body).to_le_bytes());
+        blob.extend(body);
+        blob
+    }
+
+    #[test]
+    fn module_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn module_round_trip() {
+        let module = sample();
+
+        let mut blob = vec![];
+        module.encode(&mut blob).unwrap();
+        assert_eq!(&blob[..14], b"nib\0\x01\0\0\0\x01\0\0\0\0\0");
+
+        let decoded: Module = decode(&mut blob.as_slice()).unwrap();
+        assert_eq!(decoded, module);
+        assert_eq!(decoded.functions()[0].name.as_deref(), Some("main"));
+        assert_eq!(decoded.functions()[1].name, None);
+
+        let functions = decoded.executor_functions();
+        let mut exe = Executor::new(&functions);
+        exe.call(0, 0).unwrap();
+        assert_eq!(exe.run(), Ok(ExecutionSuccess::End(8)));
+    }
+
+    #[test]
+    fn module_requirements() {
+        let mut module = sample();
+        assert_eq!(module.isa_version(), 1);
+        assert_eq!(module.capabilities(), Capabilities::empty());
+
+        let program = assemble("sqt f32 loc(0)\nret u8 emp").unwrap();
+        module.push(ModuleFunction::new(4, program));
+        assert_eq!(module.isa_version(), 1);
+        assert_eq!(module.capabilities(), Capabilities::FLOATS);
+
+        let mut blob = vec![];
+        module.encode(&mut blob).unwrap();
+        assert_eq!(&blob[8..14], b"\x01\0\x01\0\0\0");
+
+        let decoded: Module = decode(&mut blob.as_slice()).unwrap();
+        assert_eq!(decoded, module);
+    }
+
+    #[test]
+    fn module_errors() {
+        let mut blob = vec![];
+        sample().encode(&mut blob).unwrap();
+
+        let truncated = &blob[..blob.len() - 1];
+        let res: Result<Module, _> = decode(&mut &truncated[..]);
+        assert!(matches!(res, Err(ModuleError::IntegrityError { .. })));
+
+        let res: Result<Module, _> = decode_with(&mut &truncated[..], Checksum::Skip);
+        assert!(matches!(res, Err(ModuleError::UnexpectedEnd)));
+
+        let mut corrupted = blob.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        let res: Result<Module, _> = decode(&mut corrupted.as_slice());
+        assert!(matches!(res, Err(ModuleError::IntegrityError { .. })));
+
+        let res: Result<Module, _> = decode(&mut &b"nix\0\x01\0\0\0"[..]);
+        assert!(matches!(res, Err(ModuleError::IncorrectMagic)));
+
+        let res: Result<Module, _> = decode(&mut &b"nib\0\x02\0\0\0"[..]);
+        assert!(matches!(res, Err(ModuleError::UnsupportedVersion(2))));
+
+        let res: Result<Module, _> = decode(&mut &b"nib\0\x01\0\0\0\x09\0"[..]);
+        assert!(matches!(
+            res,
+            Err(ModuleError::UnsupportedIsa {
+                required: 9,
+                supported: op_codes::ISA_VERSION,
+            })
+        ));
+
+        let res: Result<Module, _> = decode(&mut &b"nib\0\x01\0\0\0\x01\0\x07\0\0\0"[..]);
+        let err = res.unwrap_err();
+        assert!(matches!(
+            err,
+            ModuleError::MissingCapabilities(caps) if caps == Capabilities::ATOMICS.union(Capabilities::VECTORS)
+        ));
+        assert_eq!(err.to_string(), "missing capabilities: atomics, vectors");
+
+        let res: Result<Module, _> = decode(&mut with_header(&[]).as_slice());
+        assert!(matches!(
+            res,
+            Err(ModuleError::MissingSection(sections::CODE))
+        ));
+
+        // A function whose code range is past the code section
+        let mut body = vec![sections::CODE, 1, 0, 0, 0, op_codes::NOP];
+        body.extend(&[sections::FUNCTIONS, 20, 0, 0, 0, 1, 0, 0, 0]);
+        body.extend(&[4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]);
+        let res: Result<Module, _> = decode(&mut with_header(&body).as_slice());
+        assert!(matches!(res, Err(ModuleError::IncorrectFunction(0))));
+    }
+}
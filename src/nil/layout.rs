@@ -42,6 +42,10 @@ impl<'t> Ty<'t> {
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn size(&self, layouts: &[Layout]) -> UWord {
         match self {
             Ty::OpType(op) => op.size(),
@@ -7,6 +7,7 @@ pub enum LayoutError {
     UnexpectedIndirection,
 }
 
+#[derive(Default)]
 pub struct LayoutBuilder<'n> {
     blocks: Vec<Block<'n>>,
 }
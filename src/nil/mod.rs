@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 mod layout;
 mod layout_builder;
 mod parser;
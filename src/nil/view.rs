@@ -44,7 +44,7 @@ impl<T> std::ops::Deref for View<T> {
 
 impl<T> AsRef<[T]> for View<T> {
     fn as_ref(&self) -> &[T] {
-        &*self
+        self
     }
 }
 
@@ -0,0 +1,298 @@
+use crate::common::*;
+
+const WORD: UWord = std::mem::size_of::<UWord>() as UWord;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ValidationErrorKind {
+    /// Writing to a `Val` or `Ref` operand.
+    WriteToConstant,
+    /// An empty operand where a value is required.
+    EmptyOperand,
+    /// A bitwise or shift operation on a float type.
+    FloatBitwise,
+    /// A float operation on an integer type.
+    IntegerFloatOp,
+    /// A local variable outside of the function frame.
+    LocalOutOfRange,
+    /// The program may run past its last operation.
+    MissingEnd,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ValidationError {
+    pub kind: ValidationErrorKind,
+    /// Index of the operation in the program.
+    pub idx: usize,
+}
+
+impl std::fmt::Display for ValidationErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ValidationErrorKind::*;
+
+        match self {
+            WriteToConstant => write!(f, "writing to a constant"),
+            EmptyOperand => write!(f, "empty operand"),
+            FloatBitwise => write!(f, "bitwise operation on a float type"),
+            IntegerFloatOp => write!(f, "float operation on an integer type"),
+            LocalOutOfRange => write!(f, "local out of the frame"),
+            MissingEnd => write!(f, "missing end of the program"),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "operation {}: {}", self.idx, self.kind)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Access {
+    Read,
+    Write,
+    ReadOrEmp,
+    WriteOrEmp,
+}
+
+/// Calls `f` with each operand, its access and the number of bytes it takes.
+fn for_each_operand<F>(op: &Op, mut f: F)
+where
+    F: FnMut(Operand, Access, UWord),
+{
+    use Op::*;
+
+    let mut un = |un: &UnOp, access, size| {
+        if let UnOp::First { offset, .. } = un {
+            f(*offset, Access::Read, WORD);
+        }
+
+        f(un.x(), access, size);
+    };
+
+    match op {
+        Nop | Fls => (),
+        End(x) | Slp(x) | Go(x) | Jmp(x) | App(x) | Clf(x) | Sfd(x) => f(*x, Access::Read, WORD),
+        Gfd(x) => f(*x, Access::Write, WORD),
+        Cnv(x, y, from, to) => {
+            f(*x, Access::Write, to.size());
+            f(*y, Access::Read, from.size());
+        }
+        Shl(x, y, ot) | Shr(x, y, ot) => {
+            f(*x, Access::Write, ot.size());
+            f(*y, Access::Read, 1);
+        }
+        Set(bin, ot)
+        | Add(bin, ot)
+        | Sub(bin, ot)
+        | Mul(bin, ot)
+        | Div(bin, ot)
+        | Mod(bin, ot)
+        | And(bin, ot)
+        | Or(bin, ot)
+        | Xor(bin, ot)
+        | Min(bin, ot)
+        | Max(bin, ot) => for_each_bin(bin, Access::Write, Access::Read, ot.size(), f),
+        Ife(bin, ot)
+        | Ifl(bin, ot)
+        | Ifg(bin, ot)
+        | Ine(bin, ot)
+        | Inl(bin, ot)
+        | Ing(bin, ot)
+        | Ifa(bin, ot)
+        | Ifo(bin, ot)
+        | Ifx(bin, ot)
+        | Ina(bin, ot)
+        | Ino(bin, ot)
+        | Inx(bin, ot) => for_each_bin(bin, Access::Read, Access::Read, ot.size(), f),
+        In(bin) => for_each_bin(bin, Access::Write, Access::WriteOrEmp, 1, f),
+        Not(x, ot) | Neg(x, ot) | Inc(x, ot) | Dec(x, ot) | Sqt(x, ot) | Abs(x, ot) => {
+            un(x, Access::Write, ot.size())
+        }
+        Ift(x, ot) | Iff(x, ot) | Par(x, ot) => un(x, Access::Read, ot.size()),
+        Out(x) => un(x, Access::Read, 1),
+        Ret(x, ot) => un(x, Access::ReadOrEmp, ot.size()),
+        Zer(x, y) => {
+            f(*x, Access::Read, WORD);
+            f(*y, Access::Read, WORD);
+        }
+        Cmp(x, y, z) | Cpy(x, y, z) => {
+            f(*x, Access::Read, WORD);
+            f(*y, Access::Read, WORD);
+            f(*z, Access::Read, WORD);
+        }
+    }
+}
+
+fn for_each_bin<F>(bin: &BinOp, left: Access, right: Access, size: UWord, mut f: F)
+where
+    F: FnMut(Operand, Access, UWord),
+{
+    let (x, y) = match *bin {
+        BinOp::None { x, y } => (x, y),
+        BinOp::First { x, y, offset }
+        | BinOp::Second { x, y, offset }
+        | BinOp::Both { x, y, offset } => {
+            f(offset, Access::Read, WORD);
+            (x, y)
+        }
+    };
+
+    f(x, left, size);
+    f(y, right, size);
+}
+
+fn is_bitwise_float(op: &Op) -> bool {
+    use Op::*;
+
+    let ot = match op {
+        Shl(.., ot) | Shr(.., ot) | Not(_, ot) => ot,
+        And(_, ot) | Or(_, ot) | Xor(_, ot) => ot,
+        Ifa(_, ot) | Ifo(_, ot) | Ifx(_, ot) | Ina(_, ot) | Ino(_, ot) | Inx(_, ot) => ot,
+        _ => return false,
+    };
+
+    ot.is_float()
+}
+
+fn is_terminal(op: &Op) -> bool {
+    matches!(op, Op::End(_) | Op::Ret(..) | Op::Go(_) | Op::Jmp(_))
+}
+
+fn check_frame(
+    loc: UWord,
+    size: UWord,
+    frame_size: Option<UWord>,
+) -> Result<(), ValidationErrorKind> {
+    match (loc.checked_add(size), frame_size) {
+        (Some(end), Some(frame_size)) if end <= frame_size => Ok(()),
+        (_, None) => Ok(()),
+        _ => Err(ValidationErrorKind::LocalOutOfRange),
+    }
+}
+
+fn validate_op(op: &Op, frame_size: Option<UWord>) -> Result<(), ValidationErrorKind> {
+    if is_bitwise_float(op) {
+        return Err(ValidationErrorKind::FloatBitwise);
+    }
+
+    if let Op::Sqt(_, ot) = op {
+        if !ot.is_float() {
+            return Err(ValidationErrorKind::IntegerFloatOp);
+        }
+    }
+
+    let mut res = Ok(());
+    for_each_operand(op, |operand, access, size| {
+        if res.is_err() {
+            return;
+        }
+
+        res = match (operand, access) {
+            (Operand::Emp, Access::ReadOrEmp) | (Operand::Emp, Access::WriteOrEmp) => Ok(()),
+            (Operand::Emp, _) => Err(ValidationErrorKind::EmptyOperand),
+            (Operand::Val(_), Access::Write) | (Operand::Val(_), Access::WriteOrEmp) => {
+                Err(ValidationErrorKind::WriteToConstant)
+            }
+            (Operand::Ref(_), Access::Write) | (Operand::Ref(_), Access::WriteOrEmp) => {
+                Err(ValidationErrorKind::WriteToConstant)
+            }
+            // The pointer of `Ind` is a word in the frame
+            (Operand::Loc(loc), _) => check_frame(loc, size, frame_size),
+            (Operand::Ind(loc), _) => check_frame(loc, WORD, frame_size),
+            _ => Ok(()),
+        };
+    });
+
+    res
+}
+
+fn validate_program(program: &[Op], frame_size: Option<UWord>) -> Result<(), ValidationError> {
+    for (idx, op) in program.iter().enumerate() {
+        validate_op(op, frame_size).map_err(|kind| ValidationError { kind, idx })?;
+    }
+
+    // The last operation must not fall through, even when it's skipped by a condition
+    let ends = match program {
+        [] => false,
+        [last] => is_terminal(last),
+        [.., prev, last] => is_terminal(last) && !prev.is_conditional(),
+    };
+
+    if ends {
+        Ok(())
+    } else {
+        Err(ValidationError {
+            kind: ValidationErrorKind::MissingEnd,
+            idx: program.len(),
+        })
+    }
+}
+
+/// Validates the program before execution.
+pub fn validate(program: &[Op]) -> Result<(), ValidationError> {
+    validate_program(program, None)
+}
+
+/// Validates the program and checks that all locals fit the frame.
+pub fn validate_with_frame(program: &[Op], frame_size: UWord) -> Result<(), ValidationError> {
+    validate_program(program, Some(frame_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    fn check(code: &str) -> Result<(), ValidationError> {
+        validate_with_frame(&assemble(code).unwrap(), 8)
+    }
+
+    fn error(kind: ValidationErrorKind, idx: usize) -> Result<(), ValidationError> {
+        Err(ValidationError { kind, idx })
+    }
+
+    #[test]
+    fn validate_correct() {
+        let code = r#"
+            set u32 loc(0) 12
+            add u32 loc(4){loc(0)} *0
+            ifl u32 loc(0) 20
+            inc u32 ret(0)
+            in loc(4) emp
+            ret u8 emp
+        "#;
+
+        assert_eq!(check(code), Ok(()));
+    }
+
+    #[test]
+    fn validate_errors() {
+        use ValidationErrorKind::*;
+
+        assert_eq!(check("set u8 val(1) 2\nend 0"), error(WriteToConstant, 0));
+        assert_eq!(
+            check("nop\nin loc(0) ref(0)\nend 0"),
+            error(WriteToConstant, 1)
+        );
+        assert_eq!(check("set u8 loc(0) emp\nend 0"), error(EmptyOperand, 0));
+        assert_eq!(check("and f32 loc(0) 1\nend 0"), error(FloatBitwise, 0));
+        assert_eq!(check("shl f64 loc(0) 1\nend 0"), error(FloatBitwise, 0));
+        assert_eq!(check("sqt u32 loc(0)\nend 0"), error(IntegerFloatOp, 0));
+        assert_eq!(check("set u8 loc(8) 1\nend 0"), error(LocalOutOfRange, 0));
+        assert_eq!(check("set u32 loc(6) 1\nend 0"), error(LocalOutOfRange, 0));
+        assert_eq!(check("set u64 loc(0) 1\nend 0"), Ok(()));
+        assert_eq!(
+            check("set u8 loc(0){*9} 1\nend 0"),
+            error(LocalOutOfRange, 0)
+        );
+        assert_eq!(check("set u8 loc(0) 1"), error(MissingEnd, 1));
+        assert_eq!(check("ift u8 loc(0)\nend 0"), error(MissingEnd, 2));
+        assert_eq!(validate(&[]), error(MissingEnd, 0));
+        assert_eq!(
+            validate(&assemble("set u8 loc(9) 1\nend 0").unwrap()),
+            Ok(())
+        );
+    }
+}